@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+
+// local clones that no team repo maps to but whose origin is one of the new repos,
+// e.g. manual clones into a differently named directory.
+pub fn find_candidates(
+    new_repos: &Vec<RemoteRepo>,
+    local_repos: &Vec<LocalRepo>,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
+) -> Vec<(RemoteRepo, LocalRepo)> {
+    let mut candidates = Vec::new();
+    for local_repo in local_repos {
+        let is_managed = remote_repos.iter().any(|remote_repo| {
+            git::local_dir_name(remote_repo, github_team_prefix, directories) == local_repo.name
+        });
+        if is_managed {
+            continue;
+        }
+        let Some(url) = git::remote_url(&local_repo.path) else {
+            continue;
+        };
+        if let Some(new_repo) = new_repos
+            .iter()
+            .find(|new_repo| git::is_same_remote(&url, new_repo))
+            && !candidates
+                .iter()
+                .any(|(candidate, _): &(RemoteRepo, LocalRepo)| candidate.name == new_repo.name)
+        {
+            candidates.push((new_repo.clone(), local_repo.clone()));
+        }
+    }
+    candidates
+}
@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use reqwest::Client;
+use reqwest::Method;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::Url;
+use reqwest::header::ACCEPT;
+use reqwest::header::USER_AGENT;
+use tokio::sync::Semaphore;
+
+use crate::git::RemoteRepo;
+use crate::read_only;
+
+// knobs for corporate proxies that choke on the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct HttpOptions {
+    pub connect_timeout: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub pool_size: Option<usize>,
+    pub http2: bool,
+    pub user_agent_suffix: Option<String>,
+}
+
+// recorded API responses by request url, for reproducible demos and offline debugging.
+#[derive(Debug)]
+enum Fixtures {
+    Record {
+        path: PathBuf,
+        responses: Mutex<BTreeMap<String, serde_json::Value>>,
+    },
+    Replay(BTreeMap<String, serde_json::Value>),
+}
+
+fn request_key(url: &Url, query: &[(&str, &str)]) -> String {
+    let mut url = url.clone();
+    url.query_pairs_mut().extend_pairs(query);
+    url.to_string()
+}
+
+#[derive(Debug)]
+struct Budget {
+    window_start: Instant,
+    used: u32,
+    // x-ratelimit-remaining of the last response, GitHub's own limit for the token.
+    remaining_upstream: Option<u32>,
+}
+
+// every GitHub request goes through this client, so optional API calls can't exhaust the team token.
+#[derive(Debug)]
+pub struct ApiClient {
+    client: Client,
+    user_agent: String,
+    token: String,
+    requests_per_hour: u32,
+    concurrency: Semaphore,
+    budget: Mutex<Budget>,
+    fixtures: Option<Fixtures>,
+}
+
+impl ApiClient {
+    pub fn new(
+        token: String,
+        requests_per_hour: u32,
+        max_concurrency: usize,
+        http_options: &HttpOptions,
+    ) -> Result<ApiClient, String> {
+        let mut builder = Client::builder();
+        if let Some(connect_timeout) = http_options.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(timeout) = http_options.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(pool_size) = http_options.pool_size {
+            builder = builder.pool_max_idle_per_host(pool_size);
+        }
+        if !http_options.http2 {
+            builder = builder.http1_only();
+        }
+        let user_agent = match &http_options.user_agent_suffix {
+            Some(suffix) => format!("reposync {}", suffix),
+            None => "reposync".to_string(),
+        };
+        Ok(ApiClient {
+            client: builder.build().map_err(|e| e.to_string())?,
+            user_agent,
+            token,
+            requests_per_hour,
+            concurrency: Semaphore::new(max_concurrency.max(1)),
+            budget: Mutex::new(Budget {
+                window_start: Instant::now(),
+                used: 0,
+                remaining_upstream: None,
+            }),
+            fixtures: None,
+        })
+    }
+
+    // every response is written to the fixtures file as it arrives.
+    pub fn record(&mut self, path: PathBuf) {
+        self.fixtures = Some(Fixtures::Record {
+            path,
+            responses: Mutex::new(BTreeMap::new()),
+        });
+    }
+
+    // answers requests from the fixtures file only, without touching the network or the budget.
+    pub fn replay(&mut self, path: &PathBuf) -> Result<(), String> {
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let responses = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        self.fixtures = Some(Fixtures::Replay(responses));
+        Ok(())
+    }
+
+    fn spend(&self) -> Result<(), String> {
+        let mut budget = self.budget.lock().unwrap();
+        if budget.window_start.elapsed() >= Duration::from_secs(60 * 60) {
+            budget.window_start = Instant::now();
+            budget.used = 0;
+        }
+        if budget.used >= self.requests_per_hour {
+            return Err(format!(
+                "API budget of {} requests per hour is used up",
+                self.requests_per_hour
+            ));
+        }
+        if budget.remaining_upstream == Some(0) {
+            return Err("GitHub rate limit of the token is exhausted".to_string());
+        }
+        budget.used += 1;
+        Ok(())
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, String> {
+        let _permit = self
+            .concurrency
+            .acquire()
+            .await
+            .map_err(|e| e.to_string())?;
+        self.spend()?;
+        let response = request
+            .header(ACCEPT, "application/vnd.github.v3+json")
+            .header(USER_AGENT, &self.user_agent)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let remaining_upstream = response
+            .headers()
+            .get("x-ratelimit-remaining")
+            .and_then(|remaining| remaining.to_str().ok())
+            .and_then(|remaining| remaining.parse::<u32>().ok());
+        if remaining_upstream.is_some() {
+            self.budget.lock().unwrap().remaining_upstream = remaining_upstream;
+        }
+        Ok(response)
+    }
+
+    pub async fn get(&self, url: Url, query: &[(&str, &str)]) -> Result<Response, String> {
+        self.send(self.client.get(url).query(query)).await
+    }
+
+    // changes on GitHub are neither recorded nor replayed, a replayed run must not change anything.
+    pub async fn send_json(
+        &self,
+        method: Method,
+        url: Url,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        if let Some(Fixtures::Replay(_)) = &self.fixtures {
+            return Err(format!("{} {} is not possible with --replay", method, url));
+        }
+        if read_only::is_read_only() {
+            return Err(format!(
+                "{} {} is not possible with --read-only",
+                method, url
+            ));
+        }
+        let response = self
+            .send(self.client.request(method, url).json(body))
+            .await?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            // GitHub explains errors in a message field.
+            let message = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|error| error["message"].as_str().map(|message| message.to_string()))
+                .unwrap_or(text);
+            return Err(format!("{}: {}", status, message));
+        }
+        if text.trim().is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    pub async fn get_text(&self, url: Url, query: &[(&str, &str)]) -> Result<String, String> {
+        let key = request_key(&url, query);
+        if let Some(Fixtures::Replay(responses)) = &self.fixtures {
+            return match responses.get(&key) {
+                Some(body) => Ok(body.to_string()),
+                None => Err(format!("no recorded response for {}", key)),
+            };
+        }
+        let body = self
+            .get(url, query)
+            .await?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(Fixtures::Record { path, responses }) = &self.fixtures {
+            let mut responses = responses.lock().unwrap();
+            responses.insert(
+                key,
+                serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body.clone())),
+            );
+            let json = serde_json::to_string_pretty(&*responses).map_err(|e| e.to_string())?;
+            fs::write(path, json + "\n")
+                .map_err(|e| format!("failed to record to {}: {}", path.display(), e))?;
+        }
+        Ok(body)
+    }
+}
+
+pub async fn get_repos(
+    api_client: &ApiClient,
+    page: i32,
+    github_team_prefix: &String,
+    github_team_repo_url: &Url,
+) -> Result<Option<Vec<RemoteRepo>>, String> {
+    let body = api_client
+        .get_text(
+            github_team_repo_url.clone(),
+            &[("per_page", "100"), ("page", page.to_string().as_str())],
+        )
+        .await
+        .map_err(|e| format!("request for page {} failed: {}", page, e))?;
+
+    match serde_json::from_str::<Vec<RemoteRepo>>(&body) {
+        Ok(repos) => {
+            let next_paged_repos = repos
+                .into_iter()
+                .filter(|repo| repo.name.starts_with(github_team_prefix.as_str()))
+                .collect::<Vec<RemoteRepo>>();
+            if next_paged_repos.is_empty() {
+                return Ok(None);
+            } else {
+                return Ok(Some(next_paged_repos));
+            }
+        }
+
+        Err(e) => {
+            return Err(format!("failed to parse page {}: {}", page, e));
+        }
+    }
+}
+// a failed page fails the whole list, a truncated list would make the missing repos look gone.
+pub async fn get_all_repos(
+    api_client: &ApiClient,
+    github_team_prefix: &String,
+    github_team_repo_url: &Url,
+) -> Result<Vec<RemoteRepo>, String> {
+    let mut repos: Vec<RemoteRepo> = Vec::new();
+    let mut page = 1;
+    while let Some(page_repos) =
+        get_repos(api_client, page, github_team_prefix, github_team_repo_url).await?
+    {
+        repos.extend(page_repos);
+        page += 1;
+    }
+    return Ok(repos);
+}
+
+// e.g. https://api.github.com, or https://github.example.com/api/v3 for GitHub Enterprise.
+pub fn api_base(github_team_repo_url: &Url) -> String {
+    let url = github_team_repo_url.as_str();
+    for marker in ["/organizations/", "/orgs/"] {
+        if let Some(index) = url.find(marker) {
+            return url[..index].to_string();
+        }
+    }
+    github_team_repo_url.origin().ascii_serialization()
+}
+
+fn api_url(github_team_repo_url: &Url, path: &str) -> Result<Url, String> {
+    Url::parse(&format!("{}{}", api_base(github_team_repo_url), path)).map_err(|e| e.to_string())
+}
+
+pub async fn create_from_template(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    template: &str,
+    owner: &str,
+    name: &str,
+    private: bool,
+) -> Result<RemoteRepo, String> {
+    let url = api_url(
+        github_team_repo_url,
+        &format!("/repos/{}/generate", template),
+    )?;
+    let body = serde_json::json!({ "owner": owner, "name": name, "private": private });
+    let repo = api_client.send_json(Method::POST, url, &body).await?;
+    serde_json::from_value(repo).map_err(|e| e.to_string())
+}
+
+// the team repo url doubles as the endpoint that adds repos to the team.
+pub async fn add_to_team(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    owner: &str,
+    name: &str,
+) -> Result<(), String> {
+    let url = Url::parse(&format!(
+        "{}/{}/{}",
+        github_team_repo_url.as_str().trim_end_matches('/'),
+        owner,
+        name
+    ))
+    .map_err(|e| e.to_string())?;
+    let body = serde_json::json!({ "permission": "push" });
+    api_client.send_json(Method::PUT, url, &body).await?;
+    Ok(())
+}
+
+// GitHub follows transfers and renames, so the repo comes back under its current name.
+pub async fn get_repo(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    full_name: &str,
+) -> Result<RemoteRepo, String> {
+    let url = api_url(github_team_repo_url, &format!("/repos/{}", full_name))?;
+    let body = api_client.get_text(url, &[]).await?;
+    // an error like 404 Not Found comes as a message without a repo.
+    serde_json::from_str(&body).map_err(|_| {
+        serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|error| error["message"].as_str().map(|message| message.to_string()))
+            .unwrap_or(body)
+    })
+}
+
+pub async fn archive_repo(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    full_name: &str,
+) -> Result<(), String> {
+    let url = api_url(github_team_repo_url, &format!("/repos/{}", full_name))?;
+    let body = serde_json::json!({ "archived": true });
+    api_client.send_json(Method::PATCH, url, &body).await?;
+    Ok(())
+}
+
+pub async fn rename_repo(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    full_name: &str,
+    new_name: &str,
+) -> Result<RemoteRepo, String> {
+    let url = api_url(github_team_repo_url, &format!("/repos/{}", full_name))?;
+    let body = serde_json::json!({ "name": new_name });
+    let repo = api_client.send_json(Method::PATCH, url, &body).await?;
+    serde_json::from_value(repo).map_err(|e| e.to_string())
+}
+
+// open pull requests of a repo, paged like the repo list.
+pub async fn open_pull_request_count(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    full_name: &str,
+) -> Result<usize, String> {
+    let url = api_url(github_team_repo_url, &format!("/repos/{}/pulls", full_name))?;
+    let mut count = 0;
+    let mut page = 1;
+    loop {
+        let body = api_client
+            .get_text(
+                url.clone(),
+                &[
+                    ("state", "open"),
+                    ("per_page", "100"),
+                    ("page", page.to_string().as_str()),
+                ],
+            )
+            .await?;
+        let pulls =
+            serde_json::from_str::<Vec<serde_json::Value>>(&body).map_err(|e| e.to_string())?;
+        count += pulls.len();
+        if pulls.len() < 100 {
+            return Ok(count);
+        }
+        page += 1;
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use clap::ArgMatches;
+use reqwest::Url;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::prompt;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::style;
+
+pub const ARCHIVE_DIR: &str = ".reposync-archive";
+pub const ARCHIVED_MARKER: &str = "ARCHIVED";
+
+// the REST API has no archived_at for most repos, archiving bumps updated_at though.
+pub fn archived_at(remote_repo: &RemoteRepo) -> Option<DateTime<Utc>> {
+    remote_repo
+        .archived_at
+        .as_ref()
+        .or(remote_repo.updated_at.as_ref())
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+pub fn is_within_grace_period(remote_repo: &RemoteRepo, grace_period: Duration) -> bool {
+    let Some(archived_at) = archived_at(remote_repo) else {
+        return false;
+    };
+    match Utc::now().signed_duration_since(archived_at).to_std() {
+        Ok(age) => age < grace_period,
+        // archived "in the future" due to clock skew.
+        Err(_) => true,
+    }
+}
+
+fn check_git(output: Result<std::process::Output, std::io::Error>) -> Result<(), String> {
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(String::from_utf8_lossy(output.stderr.trim_ascii()).to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// keeps the clone but makes sure nobody keeps developing in it by accident.
+fn mark_read_only(local_repo: &LocalRepo) -> Result<String, String> {
+    let marker = local_repo.path.join(ARCHIVED_MARKER);
+    if marker.exists() {
+        return Ok("archived, read-only".to_string());
+    }
+    check_git(git::set_config(
+        &local_repo.path,
+        "receive.denyCurrentBranch",
+        "refuse",
+    ))?;
+    check_git(git::set_push_url(&local_repo.path, "DISABLED"))?;
+    fs::write(
+        &marker,
+        "This repo is archived on GitHub. reposync disabled pushing to it.\n",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok("archived, marked read-only".to_string())
+}
+
+pub fn apply_action(local_repo: &LocalRepo, action: &str) -> Result<String, String> {
+    match action {
+        "move" => {
+            let repo_root_dir = local_repo
+                .path
+                .parent()
+                .ok_or("repo has no parent dir".to_string())?;
+            let archive_path = repo_root_dir.join(ARCHIVE_DIR).join(&local_repo.name);
+            if archive_path.exists() {
+                return Err(format!("{} already exists", archive_path.display()));
+            }
+            fs::create_dir_all(repo_root_dir.join(ARCHIVE_DIR)).map_err(|e| e.to_string())?;
+            fs::rename(&local_repo.path, &archive_path).map_err(|e| e.to_string())?;
+            Ok(format!("archived, moved to {}", archive_path.display()))
+        }
+        "delete" => {
+            // deleting must never lose work that only exists locally.
+            match git::dirty_files(&local_repo.path) {
+                Some(dirty_files) if dirty_files.is_empty() => (),
+                Some(_) => return Err("not deleted, the working tree is dirty".to_string()),
+                None => return Err("not deleted, git status failed".to_string()),
+            }
+            match git::unpushed_commit_count(&local_repo.path) {
+                Some(0) => (),
+                Some(count) => return Err(format!("not deleted, {} unpushed commits", count)),
+                None => return Err("not deleted, unpushed commits are unknown".to_string()),
+            }
+            match git::stash_count(&local_repo.path) {
+                Some(0) => (),
+                Some(count) => return Err(format!("not deleted, {} stashed changes", count)),
+                None => return Err("not deleted, stashed changes are unknown".to_string()),
+            }
+            fs::remove_dir_all(&local_repo.path).map_err(|e| e.to_string())?;
+            Ok("archived, deleted".to_string())
+        }
+        "readonly" => mark_read_only(local_repo),
+        _ => Ok("archived".to_string()),
+    }
+}
+
+// decommissions a team repo: archives it on GitHub after confirmation, then applies the
+// archive action to its local clone right away instead of on the next sync.
+async fn decommission(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    remote_repos: &Vec<RemoteRepo>,
+    name: &str,
+    action: &str,
+) -> Result<String, String> {
+    let directories = state::load().directories;
+    let Some(remote_repo) = remote_repos.iter().find(|remote_repo| {
+        remote_repo.name == name
+            || git::local_dir_name(remote_repo, github_team_prefix, &directories) == name
+    }) else {
+        return Err(format!("{} is no team repo", name));
+    };
+    if !remote_repo.archived {
+        let full_name = remote_repo
+            .full_name()
+            .ok_or(format!("no owner in {}", remote_repo.clone_url))?;
+        if !prompt::confirm(&format!("archive {} on GitHub?", full_name)) {
+            return Ok("not archived".to_string());
+        }
+        api::archive_repo(api_client, github_team_repo_url, &full_name).await?;
+    }
+    let local_repos = list_local_repos(repo_root_dir);
+    match git::find_local_repo(remote_repo, &local_repos, github_team_prefix, &directories) {
+        Some(local_repo) => apply_action(local_repo, action),
+        None => Ok("archived, no local clone".to_string()),
+    }
+}
+
+pub async fn run(
+    cli: &ArgMatches,
+    provider: &dyn Provider,
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    archive_args: &ArgMatches,
+) {
+    let name = archive_args.get_one::<String>("repo").unwrap();
+    let remote_repos = provider::list_remote_repos(provider).await;
+    match decommission(
+        api_client,
+        github_team_repo_url,
+        github_team_prefix,
+        repo_root_dir,
+        &remote_repos,
+        name,
+        cli.get_one::<String>("archive_action").unwrap(),
+    )
+    .await
+    {
+        Ok(message) => println!("{}: {}", style::paint(name, style::WARNING), message),
+        Err(e) => {
+            println!("{}: {}", style::paint(name, style::ERROR), e);
+            std::process::exit(1);
+        }
+    }
+}
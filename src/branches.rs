@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::list_local_repos;
+use crate::prompt;
+use crate::style;
+
+#[derive(Debug, Clone)]
+pub struct StaleBranch {
+    pub repo: String,
+    pub path: PathBuf,
+    pub branch: String,
+    pub reason: String,
+}
+
+// the checked out branch and the default branch are never reported.
+pub fn find_stale_branches(local_repos: &Vec<LocalRepo>, older_than: Duration) -> Vec<StaleBranch> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut stale_branches: Vec<StaleBranch> = Vec::new();
+    for local_repo in local_repos {
+        let default_branch = git::default_branch_ref(&local_repo.path);
+        let default_branch =
+            git::remote_branch(&local_repo.path, &default_branch).unwrap_or(&default_branch);
+        let Ok(branches) = git::list_local_branches(&local_repo.path) else {
+            continue;
+        };
+        for line in branches.lines() {
+            let fields: Vec<&str> = line.split('\x1f').collect();
+            let [head, branch, track, committer_date] = fields[..] else {
+                continue;
+            };
+            if head == "*" || branch == default_branch {
+                continue;
+            }
+            let age = now.saturating_sub(committer_date.parse::<u64>().unwrap_or(now));
+            let reason = if track == "[gone]" {
+                "upstream is gone".to_string()
+            } else if age >= older_than.as_secs() {
+                format!("last commit {} days ago", age / (24 * 60 * 60))
+            } else {
+                continue;
+            };
+            stale_branches.push(StaleBranch {
+                repo: local_repo.name.clone(),
+                path: local_repo.path.clone(),
+                branch: branch.to_string(),
+                reason,
+            });
+        }
+    }
+    stale_branches.sort_by(|a, b| a.repo.cmp(&b.repo).then(a.branch.cmp(&b.branch)));
+    stale_branches
+}
+
+pub fn run(repo_root_dir: &PathBuf, branches_args: &ArgMatches) {
+    let stale = branches_args.get_one::<Duration>("stale").unwrap();
+    let stale_branches = find_stale_branches(&list_local_repos(repo_root_dir), *stale);
+    for stale_branch in &stale_branches {
+        println!(
+            "{} {}: {}",
+            style::paint(&stale_branch.repo, style::WARNING),
+            stale_branch.branch,
+            stale_branch.reason
+        );
+    }
+    if stale_branches.is_empty() {
+        println!("no stale branches");
+    } else if branches_args.get_flag("delete")
+        && prompt::confirm(&format!("delete {} branches?", stale_branches.len()))
+    {
+        for stale_branch in &stale_branches {
+            match git::delete_branch(&stale_branch.path, &stale_branch.branch) {
+                Ok(output) if output.status.success() => {
+                    println!("{} {}: deleted", stale_branch.repo, stale_branch.branch)
+                }
+                Ok(output) => println!(
+                    "{}: {}",
+                    style::paint(
+                        &format!("{} {}", stale_branch.repo, stale_branch.branch),
+                        style::ERROR
+                    ),
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                ),
+                Err(e) => println!(
+                    "{}: {}",
+                    style::paint(
+                        &format!("{} {}", stale_branch.repo, stale_branch.branch),
+                        style::ERROR
+                    ),
+                    e
+                ),
+            }
+        }
+    }
+}
+
+// repos are given by directory name or by team repo name.
+pub fn find_repos<'a>(
+    local_repos: &'a Vec<LocalRepo>,
+    names: &Vec<String>,
+    github_team_prefix: &String,
+) -> Vec<Result<&'a LocalRepo, String>> {
+    names
+        .iter()
+        .map(|name| {
+            let dir_name = name.replace(github_team_prefix.as_str(), "");
+            local_repos
+                .iter()
+                .find(|local_repo| local_repo.name == *name || local_repo.name == dir_name)
+                .ok_or(name.clone())
+        })
+        .collect()
+}
+
+// creates or checks out the same branch in several repos, e.g. for a change across services.
+pub fn switch(
+    repo_root_dir: &PathBuf,
+    github_team_prefix: &String,
+    branch_args: &ArgMatches,
+) -> bool {
+    let Some((command, args)) = branch_args.subcommand() else {
+        return false;
+    };
+    let branch = args.get_one::<String>("branch").unwrap();
+    let create_from = if command == "create" {
+        Some(
+            args.get_one::<String>("from")
+                .map_or("HEAD", |from| from.as_str()),
+        )
+    } else {
+        None
+    };
+    let names: Vec<String> = args
+        .get_many::<String>("repos")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let local_repos = list_local_repos(repo_root_dir);
+    let mut success = true;
+    for local_repo in find_repos(&local_repos, &names, github_team_prefix) {
+        let local_repo = match local_repo {
+            Ok(local_repo) => local_repo,
+            Err(name) => {
+                success = false;
+                println!("{}: not found", style::paint(&name, style::ERROR));
+                continue;
+            }
+        };
+        match git::switch_branch(&local_repo.path, branch, create_from) {
+            Ok(output) if output.status.success() => {
+                println!(
+                    "{}: on {}",
+                    style::paint(&local_repo.name, style::OK),
+                    branch
+                )
+            }
+            Ok(output) => {
+                success = false;
+                println!(
+                    "{}: {}",
+                    style::paint(&local_repo.name, style::ERROR),
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                )
+            }
+            Err(e) => {
+                success = false;
+                println!("{}: {}", style::paint(&local_repo.name, style::ERROR), e)
+            }
+        }
+    }
+    success
+}
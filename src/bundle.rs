@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::provider;
+use crate::provider::Provider;
+use crate::queue::WorkQueue;
+use crate::state;
+use crate::style;
+
+// settings shared by a team, by argument id and environment variable. the token and
+// machine specific paths like the repo root dir are left out on purpose.
+pub const SHARED_SETTINGS: [(&str, &str); 14] = [
+    ("github_team_repo_url", "GITHUB_TEAM_REPO_URL"),
+    ("github_team_prefix", "GITHUB_TEAM_PREFIX"),
+    ("provider", "REPOSYNC_PROVIDER"),
+    ("protocol", "REPOSYNC_PROTOCOL"),
+    ("prune", "REPOSYNC_PRUNE"),
+    ("prune_tags", "REPOSYNC_PRUNE_TAGS"),
+    ("verify_signatures", "REPOSYNC_VERIFY_SIGNATURES"),
+    ("verify", "REPOSYNC_VERIFY"),
+    ("verify_pulls", "REPOSYNC_VERIFY_PULLS"),
+    ("heal", "REPOSYNC_HEAL"),
+    ("archive_action", "REPOSYNC_ARCHIVE_ACTION"),
+    ("archive_grace_period", "REPOSYNC_ARCHIVE_GRACE_PERIOD"),
+    ("clone_timeout", "REPOSYNC_CLONE_TIMEOUT"),
+    ("jobs", "REPOSYNC_JOBS"),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    // environment variable to value, only settings that differ from the defaults.
+    pub config: BTreeMap<String, String>,
+    pub repos: Vec<BundledRepo>,
+    #[serde(default)]
+    pub ignored: BTreeSet<String>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledRepo {
+    pub dir: String,
+    pub repo: RemoteRepo,
+}
+
+fn shared_config(cli: &ArgMatches) -> BTreeMap<String, String> {
+    let mut config = BTreeMap::new();
+    for (id, env) in SHARED_SETTINGS {
+        if matches!(cli.value_source(id), None | Some(ValueSource::DefaultValue)) {
+            continue;
+        }
+        if let Some(value) = cli.get_raw(id).and_then(|mut values| values.next()) {
+            config.insert(env.to_string(), value.to_string_lossy().into_owned());
+        }
+    }
+    config
+}
+
+// the team repos cloned on this machine, under the directory names used here.
+pub fn export(
+    cli: &ArgMatches,
+    path: &PathBuf,
+    local_repos: &Vec<LocalRepo>,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+) -> Result<usize, String> {
+    let sync_state = state::load();
+    let repos: Vec<BundledRepo> = remote_repos
+        .iter()
+        .filter(|remote_repo| !remote_repo.archived)
+        .filter_map(|remote_repo| {
+            git::find_local_repo(
+                remote_repo,
+                local_repos,
+                github_team_prefix,
+                &sync_state.directories,
+            )
+            .map(|local_repo| BundledRepo {
+                dir: local_repo.name.clone(),
+                repo: remote_repo.clone(),
+            })
+        })
+        .collect();
+    let count = repos.len();
+    let bundle = Bundle {
+        config: shared_config(cli),
+        repos,
+        ignored: sync_state.ignored,
+        tags: sync_state.tags,
+    };
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(path, json + "\n").map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+// a bundle comes from someone else, so its directories must stay inside the root dir.
+fn is_plain_dir(dir: &str) -> bool {
+    !dir.is_empty() && !dir.starts_with('.') && !dir.contains(['/', '\\'])
+}
+
+fn read(path: &PathBuf) -> Result<Bundle, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+// merges the shared state, clones every bundled repo that is missing and prints the
+// shared settings for the shell profile.
+pub async fn import(
+    path: &PathBuf,
+    repo_root_dir: &PathBuf,
+    protocol: &str,
+    jobs: usize,
+) -> Result<(), String> {
+    let mut bundle = read(path)?;
+    let mut failed = 0;
+    bundle.repos.retain(|bundled| {
+        if !is_plain_dir(&bundled.dir) {
+            failed += 1;
+            println!(
+                "{}: not a plain directory name, skipped",
+                style::paint(&bundled.dir, style::ERROR)
+            );
+        }
+        is_plain_dir(&bundled.dir)
+    });
+
+    let mut sync_state = state::load();
+    sync_state.ignored.extend(bundle.ignored);
+    for (tag, repos) in bundle.tags {
+        sync_state.tags.entry(tag).or_default().extend(repos);
+    }
+    for bundled in &bundle.repos {
+        sync_state
+            .directories
+            .entry(bundled.repo.name.clone())
+            .or_insert(bundled.dir.clone());
+    }
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+
+    let mut present = 0;
+    let mut work_queue = WorkQueue::new(jobs);
+    for bundled in bundle.repos {
+        if repo_root_dir.join(&bundled.dir).exists() {
+            present += 1;
+            continue;
+        }
+        let repo_root_dir = repo_root_dir.clone();
+        let protocol = protocol.to_string();
+        work_queue.push(async move {
+            let result = git::git_clone(
+                &bundled.repo,
+                repo_root_dir,
+                bundled.dir.clone(),
+                &[],
+                &protocol,
+                |_| {},
+            )
+            .await;
+            (bundled.dir, result)
+        });
+    }
+    while let Some((dir, result)) = work_queue.next().await {
+        match result {
+            Ok(output) if output.status.success() => {
+                println!("{}: cloned", style::paint(&dir, style::CLONED))
+            }
+            Ok(output) => {
+                failed += 1;
+                println!(
+                    "{}: failed to clone: {}",
+                    style::paint(&dir, style::ERROR),
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                )
+            }
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "{}: failed to clone: {}",
+                    style::paint(&dir, style::ERROR),
+                    e
+                )
+            }
+        }
+    }
+    println!(
+        "{}: {}",
+        style::paint("Already present", style::OK),
+        present
+    );
+
+    if !bundle.config.is_empty() {
+        println!("\nshared settings, add them to your shell profile:");
+        for (env, value) in &bundle.config {
+            println!("export {}='{}'", env, value.replace('\'', "'\\''"));
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} repos failed", failed));
+    }
+    Ok(())
+}
+
+pub async fn run(
+    cli: &ArgMatches,
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    github_team_prefix: &String,
+    bundle_args: &ArgMatches,
+) {
+    match bundle_args.subcommand() {
+        Some(("export", export_args)) => {
+            let path = export_args.get_one::<PathBuf>("file").unwrap();
+            let remote_repos = provider::list_remote_repos(provider).await;
+            match export(
+                cli,
+                path,
+                &list_local_repos(repo_root_dir),
+                &remote_repos,
+                github_team_prefix,
+            ) {
+                Ok(count) => println!("exported {} repos to {}", count, path.display()),
+                Err(e) => {
+                    println!(
+                        "{}: {}",
+                        style::paint(&format!("failed to write {}", path.display()), style::ERROR),
+                        e
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("import", import_args)) => {
+            let path = import_args.get_one::<PathBuf>("file").unwrap();
+            if let Err(e) = import(
+                path,
+                repo_root_dir,
+                cli.get_one::<String>("protocol").unwrap(),
+                *cli.get_one::<usize>("jobs").unwrap(),
+            )
+            .await
+            {
+                println!(
+                    "{}: {}",
+                    style::paint(
+                        &format!("failed to import {}", path.display()),
+                        style::ERROR
+                    ),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+        _ => unreachable!(),
+    }
+}
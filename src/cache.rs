@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::provider;
+use crate::provider::Provider;
+use crate::queue::WorkQueue;
+use crate::style;
+
+// refs of the dependent clones, kept in the mirror during gc.
+const PINNED_REFS: &str = "refs/reposync-dependents/";
+
+// the mirror a repo borrows from, <repo>.git for mirrors created by cache update.
+fn mirror_path(reference_dir: &PathBuf, remote_repo: &RemoteRepo) -> PathBuf {
+    let dir_name = remote_repo.dir_name.as_deref().unwrap_or(&remote_repo.name);
+    git::reference_repo(reference_dir, remote_repo, dir_name)
+        .unwrap_or(reference_dir.join(format!("{}.git", remote_repo.name)))
+}
+
+// bare repos in the cache, whoever created them.
+fn list_mirrors(reference_dir: &PathBuf) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(reference_dir) else {
+        return Vec::new();
+    };
+    let mut mirrors: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("objects").is_dir() && path.join("HEAD").is_file())
+        .collect();
+    mirrors.sort();
+    mirrors
+}
+
+// the object dirs a clone borrows from, relative entries are relative to its own objects dir.
+fn alternates(repo_path: &PathBuf) -> Vec<PathBuf> {
+    let objects = repo_path.join(".git").join("objects");
+    let Ok(content) = fs::read_to_string(objects.join("info").join("alternates")) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| objects.join(line).canonicalize().ok())
+        .collect()
+}
+
+// clones in the repo root dir that need objects of the mirror.
+fn dependents<'a>(mirror: &PathBuf, local_repos: &'a [LocalRepo]) -> Vec<&'a LocalRepo> {
+    let Ok(objects) = mirror.join("objects").canonicalize() else {
+        return Vec::new();
+    };
+    local_repos
+        .iter()
+        .filter(|local_repo| alternates(&local_repo.path).contains(&objects))
+        .collect()
+}
+
+fn git_output(path: &PathBuf, args: &[&str]) -> Result<String, String> {
+    let output = git::git_command()
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(output.stderr.trim_ascii()).to_string());
+    }
+    Ok(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// a mirror fetch drops every ref the remote doesn't have, e.g. after a force push. the
+// refs of the dependent clones are copied into the mirror, so gc keeps the objects they borrow.
+fn pin_dependents(mirror: &PathBuf, dependents: &[&LocalRepo]) -> Result<(), String> {
+    let pinned = git_output(
+        mirror,
+        &["for-each-ref", "--format=%(refname)", PINNED_REFS],
+    )?;
+    for pinned_ref in pinned.lines() {
+        git_output(mirror, &["update-ref", "-d", pinned_ref])?;
+    }
+    for dependent in dependents {
+        let pin = |e: String| format!("{}: {}", dependent.name, e);
+        let path = dependent.path.to_string_lossy();
+        git_output(
+            mirror,
+            &[
+                "fetch",
+                "--quiet",
+                "--no-tags",
+                &path,
+                &format!("+refs/*:{}{}/*", PINNED_REFS, dependent.name),
+            ],
+        )
+        .map_err(pin)?;
+        // a detached HEAD is not under refs/.
+        let head = git_output(
+            &dependent.path,
+            &["rev-parse", "--verify", "--quiet", "HEAD"],
+        );
+        if let Ok(head) = head
+            && !head.is_empty()
+        {
+            git_output(
+                mirror,
+                &[
+                    "update-ref",
+                    &format!("{}{}/HEAD", PINNED_REFS, dependent.name),
+                    &head,
+                ],
+            )
+            .map_err(pin)?;
+        }
+    }
+    Ok(())
+}
+
+// creates a mirror for every active team repo without one and fetches into the others.
+pub async fn update(
+    reference_dir: &PathBuf,
+    remote_repos: &[RemoteRepo],
+    protocol: &str,
+    jobs: usize,
+) -> Result<(), String> {
+    fs::create_dir_all(reference_dir).map_err(|e| format!("{}: {}", reference_dir.display(), e))?;
+    let mut work_queue = WorkQueue::new(jobs);
+    for remote_repo in remote_repos
+        .iter()
+        .filter(|remote_repo| !remote_repo.archived)
+    {
+        let remote_repo = remote_repo.clone();
+        let mirror = mirror_path(reference_dir, &remote_repo);
+        let protocol = protocol.to_string();
+        work_queue.push(async move {
+            let created = !mirror.exists();
+            let result = git::git_mirror(&remote_repo, &mirror, &protocol).await;
+            (remote_repo.name, created, result)
+        });
+    }
+    let mut failed = 0;
+    while let Some((name, created, result)) = work_queue.next().await {
+        match result {
+            Ok(output) if output.status.success() => {
+                if created {
+                    println!("{}: mirrored", style::paint(&name, style::CLONED))
+                } else {
+                    println!("{}: updated", style::paint(&name, style::UPDATED))
+                }
+            }
+            Ok(output) => {
+                failed += 1;
+                println!(
+                    "{}: {}",
+                    style::paint(&name, style::ERROR),
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                )
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", style::paint(&name, style::ERROR), e)
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} mirrors failed", failed));
+    }
+    Ok(())
+}
+
+// removes mirrors of repos that are gone or archived and compacts the others. a mirror
+// that clones still borrow from is never removed, and before git gc their refs are pinned
+// in the mirror. only clones in the repo root dir are known, clones elsewhere are not.
+pub fn gc(
+    reference_dir: &PathBuf,
+    remote_repos: &[RemoteRepo],
+    local_repos: &[LocalRepo],
+) -> Result<(), String> {
+    let active: Vec<PathBuf> = remote_repos
+        .iter()
+        .filter(|remote_repo| !remote_repo.archived)
+        .map(|remote_repo| mirror_path(reference_dir, remote_repo))
+        .collect();
+    let mut failed = 0;
+    for mirror in list_mirrors(reference_dir) {
+        let name = mirror
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dependents = dependents(&mirror, local_repos);
+        // an empty repo list is more likely a failed request than a team without repos.
+        if !remote_repos.is_empty() && !active.contains(&mirror) {
+            if dependents.is_empty() {
+                match fs::remove_dir_all(&mirror) {
+                    Ok(()) => println!("{}: removed", style::paint(&name, style::ARCHIVED)),
+                    Err(e) => {
+                        failed += 1;
+                        println!("{}: {}", style::paint(&name, style::ERROR), e)
+                    }
+                }
+                continue;
+            }
+            println!(
+                "{}: kept, still used by {}",
+                style::paint(&name, style::WARNING),
+                dependents
+                    .iter()
+                    .map(|dependent| dependent.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            );
+        }
+        match pin_dependents(&mirror, &dependents)
+            .and_then(|_| git_output(&mirror, &["gc", "--quiet"]).map(|_| ()))
+        {
+            Ok(()) => println!("{}: compacted", style::paint(&name, style::OK)),
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", style::paint(&name, style::ERROR), e)
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} mirrors failed", failed));
+    }
+    Ok(())
+}
+
+// the cache is the dir given with --reference-dir.
+pub async fn run(
+    cli: &ArgMatches,
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    cache_args: &ArgMatches,
+) {
+    let Some(reference_dir) = cli.get_one::<PathBuf>("reference_dir") else {
+        println!(
+            "{}: the cache is the dir given with --reference-dir",
+            style::paint("no cache dir", style::ERROR)
+        );
+        std::process::exit(1);
+    };
+    let remote_repos = provider::list_remote_repos(provider).await;
+    let result = match cache_args.subcommand() {
+        Some(("update", _)) => {
+            update(
+                reference_dir,
+                &remote_repos,
+                cli.get_one::<String>("protocol").unwrap(),
+                *cli.get_one::<usize>("jobs").unwrap(),
+            )
+            .await
+        }
+        Some(("gc", _)) => gc(
+            reference_dir,
+            &remote_repos,
+            &list_local_repos(repo_root_dir),
+        ),
+        _ => unreachable!(),
+    };
+    if let Err(e) = result {
+        println!("{}: {}", style::paint("cache", style::ERROR), e);
+        std::process::exit(1);
+    }
+}
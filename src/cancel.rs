@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+// checked before each git invocation, so cancelling stops new work while running git processes finish.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+pub const AUTH_FAILURE_THRESHOLD: usize = 5;
+
+#[derive(Debug, Default)]
+struct AuthFailures {
+    count: usize,
+    first_message: Option<String>,
+    // any success or unrelated error proves that auth is not broken for everything.
+    settled: bool,
+    tripped: bool,
+}
+
+#[derive(Debug)]
+pub struct AuthFailureDetector {
+    threshold: usize,
+    cancellation: CancellationToken,
+    failures: Mutex<AuthFailures>,
+}
+
+impl AuthFailureDetector {
+    pub fn new(threshold: usize, cancellation: CancellationToken) -> AuthFailureDetector {
+        AuthFailureDetector {
+            threshold,
+            cancellation,
+            failures: Mutex::new(AuthFailures::default()),
+        }
+    }
+
+    pub fn record_success(&self) {
+        self.failures.lock().unwrap().settled = true;
+    }
+
+    pub fn record_failure(&self, is_auth_error: bool, message: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        if failures.settled {
+            return;
+        }
+        if !is_auth_error {
+            failures.settled = true;
+            return;
+        }
+        failures.count += 1;
+        if failures.first_message.is_none() {
+            failures.first_message = Some(message.to_string());
+        }
+        if failures.count >= self.threshold {
+            failures.tripped = true;
+            self.cancellation.cancel();
+        }
+    }
+
+    pub fn diagnosis(&self) -> Option<String> {
+        let failures = self.failures.lock().unwrap();
+        if !failures.tripped {
+            return None;
+        }
+        Some(format!(
+            "the first {} git operations all failed to authenticate, remaining repos were skipped.\n{}\nCheck your SSH key and agent, e.g. with `ssh -T git@github.com`.",
+            failures.count,
+            failures.first_message.clone().unwrap_or_default()
+        ))
+    }
+}
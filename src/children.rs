@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Mutex;
+
+// process groups of the git processes that are currently running.
+static RUNNING: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+// unregisters the child's process group once it has finished or was dropped.
+pub struct Tracked(Option<u32>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if let Some(id) = self.0 {
+            RUNNING.lock().unwrap().remove(&id);
+        }
+    }
+}
+
+// starts git in its own process group, so ssh processes it starts can be terminated together with it.
+// a Ctrl-C in the terminal no longer reaches the group directly, forward_signals passes it on.
+pub fn spawn(mut command: Command) -> Result<(tokio::process::Child, Tracked), std::io::Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command.stdin(Stdio::null());
+    let child = tokio::process::Command::from(command)
+        .kill_on_drop(true)
+        .spawn()?;
+    let id = child.id();
+    if let Some(id) = id {
+        RUNNING.lock().unwrap().insert(id);
+    }
+    Ok((child, Tracked(id)))
+}
+
+#[cfg(unix)]
+fn terminate_all() {
+    for id in RUNNING.lock().unwrap().iter() {
+        // the group id of a process started with process_group(0) is its pid.
+        unsafe {
+            libc::killpg(*id as libc::pid_t, libc::SIGTERM);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate_all() {
+    // the console sends Ctrl-C to all attached processes on Windows, git included.
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::SignalKind;
+    use tokio::signal::unix::signal;
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => (),
+                _ = terminate.recv() => (),
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+// terminates all running git process groups on SIGINT or SIGTERM before exiting.
+pub fn forward_signals() {
+    tokio::spawn(async {
+        wait_for_signal().await;
+        terminate_all();
+        std::process::exit(130);
+    });
+}
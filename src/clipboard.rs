@@ -0,0 +1,53 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+// the clipboard tools of each platform, the first one that works wins.
+fn candidates() -> Vec<Command> {
+    let command = |program: &str, args: &[&str]| {
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    };
+    if cfg!(target_os = "macos") {
+        return vec![command("pbcopy", &[])];
+    }
+    if cfg!(windows) {
+        return vec![command("clip", &[])];
+    }
+    let mut candidates = Vec::new();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        candidates.push(command("wl-copy", &[]));
+    }
+    candidates.push(command("xclip", &["-selection", "clipboard"]));
+    candidates.push(command("xsel", &["--clipboard", "--input"]));
+    candidates
+}
+
+pub fn copy(text: &str) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for mut command in candidates() {
+        let program = command.get_program().to_string_lossy().to_string();
+        let mut child = match command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                errors.push(format!("{}: {}", program, e));
+                continue;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        match child.wait() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => errors.push(format!("{}: {}", program, status)),
+            Err(e) => errors.push(format!("{}: {}", program, e)),
+        }
+    }
+    Err(errors.join(", "))
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::events;
+use crate::paths;
+
+// settings that don't fit on the command line, read from config.toml in the config dir.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    // checked in order before the built-in classification of git output.
+    #[serde(default)]
+    pub classify: Vec<ClassificationRule>,
+    // by hostname, so one config in the dotfiles fits every machine.
+    #[serde(default)]
+    pub machine: BTreeMap<String, MachineOverrides>,
+    #[serde(default)]
+    pub colors: Colors,
+    #[serde(default)]
+    pub defaults: Defaults,
+    // synced into the same repo root dir next to the repos of --provider.
+    #[serde(default)]
+    pub source: Vec<Source>,
+    // the remote of the team repo in every clone, origin when not set.
+    pub remote_name: Option<String>,
+    // settings of single repos by team repo or directory name.
+    #[serde(default)]
+    pub repo: BTreeMap<String, RepoSettings>,
+    // repos outside the team that are cloned and pulled with it, e.g. upstream dependencies.
+    #[serde(default)]
+    pub external: Vec<ExternalRepo>,
+    // team repo or directory names, * matches anything. these repos start first and are
+    // never deferred by --deadline.
+    #[serde(default)]
+    pub priority: Vec<String>,
+    // where --email sends the summary of a sync.
+    pub email: Option<Email>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoSettings {
+    // e.g. upstream, when origin is a fork.
+    pub remote_name: Option<String>,
+    // kept checked out instead of the default branch, e.g. release/2024.
+    pub branch: Option<String>,
+    // kept at the newest tag of the default branch instead of its tip, for repos that are
+    // used but not developed. wins over branch.
+    #[serde(default)]
+    pub release: bool,
+}
+
+// e.g. a second GitHub team, a GitLab group through a provider plugin or a static list.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Source {
+    // in the summary, defaults to the provider.
+    pub name: Option<String>,
+    // github, static or the name of a reposync-provider-<name> plugin.
+    pub provider: String,
+    // only for github.
+    pub github_team_repo_url: Option<String>,
+    // removed from the directory names like --github_team_prefix.
+    #[serde(default)]
+    pub prefix: String,
+    pub language: Option<String>,
+    // repos need at least one of them.
+    #[serde(default)]
+    pub topics: Vec<String>,
+    // only for static.
+    #[serde(default)]
+    pub repos: Vec<StaticRepo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaticRepo {
+    pub name: String,
+    // cloned with either protocol.
+    pub url: String,
+}
+
+// only read by builds with the email feature, the others still accept the table.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "email"), allow(dead_code))]
+pub struct Email {
+    pub smtp_host: String,
+    // 587 with STARTTLS when not set, 465 uses implicit TLS.
+    pub smtp_port: Option<u16>,
+    pub username: Option<String>,
+    // prints the password, e.g. pass show smtp, so the file holds no secret.
+    pub password_command: Option<String>,
+    // e.g. reposync <reposync@example.com>.
+    pub from: String,
+    pub to: Vec<String>,
+    // sends nothing after syncs without failed repos.
+    #[serde(default)]
+    pub only_failures: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalRepo {
+    // cloned with either protocol.
+    pub url: String,
+    // the directory, defaults to the repo name in the url.
+    pub name: Option<String>,
+}
+
+impl ExternalRepo {
+    pub fn dir_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        self.url
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or(&self.url)
+            .to_string()
+    }
+}
+
+impl Source {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.provider)
+    }
+}
+
+// values for the required flags, written by reposync init. flags, environment variables
+// and machine overrides still win.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults {
+    pub provider: Option<String>,
+    pub github_team_repo_url: Option<String>,
+    pub repo_root_dir: Option<PathBuf>,
+    pub github_team_prefix: Option<String>,
+    // prints the token, e.g. gh auth token, so the file holds no secret.
+    pub token_command: Option<String>,
+}
+
+// a preset, default or colorblind, with single colors replaced by name or ANSI codes.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Colors {
+    pub preset: Option<String>,
+    pub ok: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+    pub updated: Option<String>,
+    pub cloned: Option<String>,
+    pub archived: Option<String>,
+}
+
+// replaces the defaults on one machine, flags and environment variables still win.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineOverrides {
+    pub repo_root_dir: Option<PathBuf>,
+    pub jobs: Option<usize>,
+    pub language: Option<String>,
+}
+
+impl Config {
+    // one value of the repo tables by team repo name and by directory, the directory is
+    // resolved like the one of a new clone would be.
+    fn repo_values<T: Clone>(
+        &self,
+        github_team_prefix: &str,
+        directories: &BTreeMap<String, String>,
+        value: impl Fn(&RepoSettings) -> Option<T>,
+    ) -> BTreeMap<String, T> {
+        let mut values = BTreeMap::new();
+        for (name, settings) in &self.repo {
+            let Some(value) = value(settings) else {
+                continue;
+            };
+            let dir_name = directories
+                .get(name)
+                .cloned()
+                .unwrap_or(name.replace(github_team_prefix, ""));
+            values.insert(name.clone(), value.clone());
+            values.insert(dir_name, value);
+        }
+        values
+    }
+
+    pub fn remote_names(
+        &self,
+        github_team_prefix: &str,
+        directories: &BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        self.repo_values(github_team_prefix, directories, |settings| {
+            settings.remote_name.clone()
+        })
+    }
+
+    pub fn pinned_branches(
+        &self,
+        github_team_prefix: &str,
+        directories: &BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        self.repo_values(github_team_prefix, directories, |settings| {
+            settings.branch.clone()
+        })
+    }
+
+    pub fn is_priority(&self, names: &[&str]) -> bool {
+        self.priority.iter().any(|pattern| {
+            names
+                .iter()
+                .any(|name| events::matches_pattern(pattern, name))
+        })
+    }
+
+    pub fn release_repos(
+        &self,
+        github_team_prefix: &str,
+        directories: &BTreeMap<String, String>,
+    ) -> BTreeSet<String> {
+        self.repo_values(github_team_prefix, directories, |settings| {
+            settings.release.then_some(())
+        })
+        .into_keys()
+        .collect()
+    }
+
+    // matches the full hostname or its first label, e.g. work-laptop for work-laptop.local.
+    pub fn machine_overrides(&self) -> Option<(&str, &MachineOverrides)> {
+        let hostname = hostname()?.to_lowercase();
+        let short_name = hostname.split('.').next().unwrap_or("");
+        self.machine
+            .iter()
+            .find(|(name, _)| {
+                let name = name.to_lowercase();
+                name == hostname || name == short_name
+            })
+            .map(|(name, overrides)| (name.as_str(), overrides))
+    }
+}
+
+// e.g. pattern = "proxy authentication required" with state = "auth".
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassificationRule {
+    pub pattern: String,
+    pub state: String,
+}
+
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    let result =
+        unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+    if result != 0 {
+        return None;
+    }
+    let length = buffer
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buffer.len());
+    Some(String::from_utf8_lossy(&buffer[..length]).to_string())
+}
+
+#[cfg(not(unix))]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+// runs through the shell, so the command can be written like on the command line.
+pub fn token_from_command(token_command: &str) -> Result<String, String> {
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let output = command
+        .arg(token_command)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| format!("{}: {}", token_command, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            token_command,
+            output.status,
+            String::from_utf8_lossy(output.stderr.trim_ascii())
+        ));
+    }
+    let token = String::from_utf8_lossy(output.stdout.trim_ascii()).to_string();
+    if token.is_empty() {
+        return Err(format!("{} printed no token", token_command));
+    }
+    Ok(token)
+}
+
+pub fn config_file() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+// a missing config file means defaults, a broken one is an error so typos don't go unnoticed.
+pub fn load(path: Option<&PathBuf>) -> Result<Config, String> {
+    let Some(path) = path.cloned().or_else(config_file) else {
+        return Ok(Config::default());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))
+}
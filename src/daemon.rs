@@ -0,0 +1,261 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveTime;
+use chrono::Weekday;
+use clap::ArgMatches;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::children;
+use crate::style;
+use crate::webhook;
+
+// wall clock and monotonic clock drifting apart by more than this means the machine slept.
+const SLEEP_DETECTION_SLACK: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const WEBHOOK_DEBOUNCE: Duration = Duration::from_secs(2);
+
+// e.g. 07:00-19:00, a range ending before it starts wraps past midnight like 22:00-06:00.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeRange {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeRange {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+pub fn parse_time_range(value: &str) -> Result<TimeRange, String> {
+    let (start, end) = value.split_once('-').ok_or(format!(
+        "expected HH:MM-HH:MM, e.g. 07:00-19:00, got '{}'",
+        value
+    ))?;
+    let parse = |time: &str| {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("invalid time '{}', expected HH:MM", time))
+    };
+    Ok(TimeRange {
+        start: parse(start)?,
+        end: parse(end)?,
+    })
+}
+
+// e.g. mon-fri or sat,sun.
+pub fn parse_days(value: &str) -> Result<Vec<Weekday>, String> {
+    let parse = |day: &str| {
+        day.trim()
+            .parse::<Weekday>()
+            .map_err(|_| format!("invalid day '{}', expected e.g. mon", day))
+    };
+    let mut days = Vec::new();
+    for part in value.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (mut day, last) = (parse(first)?, parse(last)?);
+                days.push(day);
+                while day != last {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse(part)?),
+        }
+    }
+    Ok(days)
+}
+
+// when syncs may run, in local time. without any restriction the daemon syncs around the clock.
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    pub active_hours: Option<TimeRange>,
+    pub active_days: Option<Vec<Weekday>>,
+    pub quiet_hours: Option<TimeRange>,
+}
+
+impl Schedule {
+    fn is_active(&self, now: DateTime<Local>) -> bool {
+        let time = now.time();
+        self.active_hours.is_none_or(|hours| hours.contains(time))
+            && self
+                .active_days
+                .as_ref()
+                .is_none_or(|days| days.contains(&now.weekday()))
+            && !self.quiet_hours.is_some_and(|hours| hours.contains(time))
+    }
+}
+
+// sleeps for one step, returns true if the machine was suspended meanwhile.
+async fn sleep_detecting_wake(step: Duration) -> bool {
+    let wall_clock_before = SystemTime::now();
+    let monotonic_before = Instant::now();
+    tokio::time::sleep(step).await;
+    let wall_clock_elapsed = SystemTime::now()
+        .duration_since(wall_clock_before)
+        .unwrap_or_default();
+    // the monotonic clock stands still while suspended, the wall clock doesn't.
+    wall_clock_elapsed > monotonic_before.elapsed() + SLEEP_DETECTION_SLACK
+}
+
+// the top level arguments of this invocation, i.e. everything before the daemon subcommand.
+fn sync_args() -> Vec<OsString> {
+    std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "daemon")
+        .collect()
+}
+
+// a sync of all repos, or of the given repos only.
+async fn sync(args: Vec<OsString>, repos: BTreeSet<String>) {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            println!("{}: {}", style::paint("sync failed", style::ERROR), e);
+            return;
+        }
+    };
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .env("REPOSYNC_EVENTS", "true")
+        .env("REPOSYNC_EMAIL", "true");
+    if !repos.is_empty() {
+        command.arg("sync").args(repos);
+    }
+    match children::spawn(command) {
+        Ok((mut child, _tracked)) => match child.wait().await {
+            Ok(status) if !status.success() => {
+                println!("{}: {}", style::paint("sync failed", style::ERROR), status)
+            }
+            Ok(_) => {}
+            Err(e) => println!("{}: {}", style::paint("sync failed", style::ERROR), e),
+        },
+        Err(e) => println!("{}: {}", style::paint("sync failed", style::ERROR), e),
+    }
+}
+
+fn queue(
+    delivery: webhook::Delivery,
+    pending: &mut BTreeSet<String>,
+    renames: &mut Vec<(String, String)>,
+) {
+    match delivery {
+        webhook::Delivery::Sync(repo) => {
+            pending.insert(repo);
+        }
+        webhook::Delivery::Renamed { old_name, new_name } => renames.push((old_name, new_name)),
+    }
+}
+
+// completes when the running sync ends, never while none runs.
+async fn finished(running: &mut Option<JoinHandle<()>>) {
+    match running {
+        Some(sync) => {
+            let _ = sync.await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+// runs a regular sync in a child process on every tick, so a failing or crashing sync
+// never takes the daemon down and every run starts with fresh state and config.
+// only one sync runs at a time, each saves the whole state file when it ends and would
+// drop what another one recorded meanwhile. repos announced by webhooks are synced as soon
+// as the running sync ends.
+async fn serve(interval: Duration, schedule: Schedule, webhook: Option<webhook::Options>) {
+    children::forward_signals();
+    let args = sync_args();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<webhook::Delivery>();
+    if let Some(webhook) = webhook.clone() {
+        tokio::spawn(webhook::listen(webhook, sender.clone()));
+    }
+    let mut pending: BTreeSet<String> = BTreeSet::new();
+    // renamed repos wait like the repos to sync, the state is changed between syncs only.
+    let mut renames: Vec<(String, String)> = Vec::new();
+    let mut next_full_sync = Instant::now();
+    let mut running: Option<JoinHandle<()>> = None;
+    loop {
+        // outside the window nothing starts, webhook repos wait for the next window.
+        if running.is_none() && schedule.is_active(Local::now()) {
+            if let Some(webhook) = &webhook {
+                for (old_name, new_name) in renames.drain(..) {
+                    webhook::adopt_renamed(webhook, &old_name, &new_name);
+                    pending.insert(new_name);
+                }
+            }
+            if Instant::now() >= next_full_sync {
+                // the full sync covers the announced repos too.
+                pending.clear();
+                running = Some(tokio::spawn(sync(args.clone(), BTreeSet::new())));
+                next_full_sync = Instant::now() + interval;
+            } else if !pending.is_empty() {
+                running = Some(tokio::spawn(sync(
+                    args.clone(),
+                    std::mem::take(&mut pending),
+                )));
+            }
+        }
+        // a sync that is due outside the window is rechecked every CHECK_INTERVAL.
+        let step = match next_full_sync.saturating_duration_since(Instant::now()) {
+            until_next_sync if until_next_sync.is_zero() => CHECK_INTERVAL,
+            until_next_sync => until_next_sync.min(CHECK_INTERVAL),
+        };
+        tokio::select! {
+            woke_up = sleep_detecting_wake(step) => {
+                if woke_up {
+                    println!("woke up from sleep, syncing now");
+                    next_full_sync = Instant::now();
+                }
+            }
+            _ = finished(&mut running) => {
+                running = None;
+            }
+            Some(delivery) = receiver.recv() => {
+                queue(delivery, &mut pending, &mut renames);
+                // a push often comes with more events, they are synced together.
+                tokio::time::sleep(WEBHOOK_DEBOUNCE).await;
+                while let Ok(delivery) = receiver.try_recv() {
+                    queue(delivery, &mut pending, &mut renames);
+                }
+            }
+        }
+    }
+}
+
+pub async fn run(repo_root_dir: &PathBuf, github_team_prefix: &String, daemon_args: &ArgMatches) {
+    let schedule = Schedule {
+        active_hours: daemon_args.get_one::<TimeRange>("active_hours").copied(),
+        active_days: daemon_args.get_one::<Vec<Weekday>>("active_days").cloned(),
+        quiet_hours: daemon_args.get_one::<TimeRange>("quiet_hours").copied(),
+    };
+    let webhook = daemon_args
+        .get_one::<SocketAddr>("webhook_listen")
+        .map(|listen| webhook::Options {
+            listen: *listen,
+            secret: daemon_args.get_one::<String>("webhook_secret").cloned(),
+            github_team_prefix: github_team_prefix.clone(),
+            repo_root_dir: repo_root_dir.clone(),
+        });
+    serve(
+        *daemon_args.get_one::<Duration>("interval").unwrap(),
+        schedule,
+        webhook,
+    )
+    .await;
+}
@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::list_local_repos;
+use crate::time_format;
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub repo: String,
+    pub date: String,
+    pub author: String,
+    pub subject: String,
+}
+
+// commits on the default branch of each repo, as known locally since the last sync.
+pub fn collect_commits(local_repos: &Vec<LocalRepo>, since: Duration) -> Vec<Commit> {
+    let mut commits: Vec<Commit> = Vec::new();
+    for local_repo in local_repos {
+        let default_branch = git::default_branch_ref(&local_repo.path);
+        let Ok(log) = git::git_log_since(&local_repo.path, &default_branch, since) else {
+            continue;
+        };
+        for line in log.lines() {
+            let mut fields = line.splitn(3, '\x1f');
+            let (Some(date), Some(author), Some(subject)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            commits.push(Commit {
+                repo: local_repo.name.clone(),
+                date: date.to_string(),
+                author: author.to_string(),
+                subject: subject.to_string(),
+            });
+        }
+    }
+    commits.sort_by(|a, b| a.repo.cmp(&b.repo).then(b.date.cmp(&a.date)));
+    commits
+}
+
+// keeps commits whose author contains one of the given names, ignoring case.
+pub fn filter_authors(commits: Vec<Commit>, authors: &Vec<String>) -> Vec<Commit> {
+    if authors.is_empty() {
+        return commits;
+    }
+    let authors: Vec<String> = authors.iter().map(|author| author.to_lowercase()).collect();
+    commits
+        .into_iter()
+        .filter(|commit| {
+            let commit_author = commit.author.to_lowercase();
+            authors.iter().any(|author| commit_author.contains(author))
+        })
+        .collect()
+}
+
+pub fn render(commits: &Vec<Commit>, format: &str, group_by: &str) -> String {
+    if commits.is_empty() {
+        return "no commits in this time window\n".to_string();
+    }
+    let group_of = |commit: &Commit| -> String {
+        match group_by {
+            "author" => commit.author.clone(),
+            _ => commit.repo.clone(),
+        }
+    };
+    let mut grouped_commits = commits.clone();
+    grouped_commits.sort_by(|a, b| group_of(a).cmp(&group_of(b)).then(b.date.cmp(&a.date)));
+
+    let mut digest = String::new();
+    let mut current_group: Option<String> = None;
+    for commit in &grouped_commits {
+        let group = group_of(commit);
+        if current_group.as_ref() != Some(&group) {
+            match format {
+                "markdown" => digest.push_str(&format!("\n## {}\n\n", group)),
+                _ => digest.push_str(&format!("{}\n", group)),
+            }
+            current_group = Some(group);
+        }
+        // the grouping key is already in the header, so each line names the other one.
+        let origin = match group_by {
+            "author" => &commit.repo,
+            _ => &commit.author,
+        };
+        match format {
+            "markdown" => digest.push_str(&format!(
+                "- {} **{}**: {}\n",
+                time_format::timestamp_str(&commit.date),
+                origin,
+                commit.subject
+            )),
+            _ => digest.push_str(&format!(
+                "  {} {}: {}\n",
+                time_format::timestamp_str(&commit.date),
+                origin,
+                commit.subject
+            )),
+        }
+    }
+    digest.trim_start().to_string()
+}
+
+pub fn run(repo_root_dir: &PathBuf, digest_args: &ArgMatches) {
+    let since = digest_args.get_one::<Duration>("since").unwrap();
+    let format = digest_args.get_one::<String>("format").unwrap();
+    let group_by = digest_args.get_one::<String>("group_by").unwrap();
+    let authors: Vec<String> = digest_args
+        .get_many::<String>("author")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let commits = filter_authors(
+        collect_commits(&list_local_repos(repo_root_dir), *since),
+        &authors,
+    );
+    print!("{}", render(&commits, format, group_by));
+}
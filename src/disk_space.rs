@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use crate::git;
+use crate::git::RemoteRepo;
+
+// bytes free for unprivileged users on the filesystem of the path or its closest existing parent.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let path = CString::new(existing.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    // no statvfs, the clones run without the check.
+    None
+}
+
+// the API reports the size of the git data in KiB, the checkout comes on top of it,
+// so this is a lower bound of what the clones need.
+pub fn required_space<'a>(new_repos: impl Iterator<Item = &'a RemoteRepo>) -> u64 {
+    new_repos
+        .filter_map(|new_repo| new_repo.size)
+        .map(|size| size * 1024)
+        .sum()
+}
+
+// an error when the clones can't fit into the free space, None when they fit or when
+// the free space is unknown.
+pub fn check(path: &Path, required: u64) -> Option<String> {
+    let free = free_space(path)?;
+    if required <= free {
+        return None;
+    }
+    Some(format!(
+        "the new repos need at least {} but only {} are free on {}",
+        git::format_size(required),
+        git::format_size(free),
+        path.display()
+    ))
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use crate::archive;
+use crate::git;
+use crate::git::LocalRepo;
+use crate::prompt;
+use crate::style;
+
+// several local directories with the same origin, usually left over from renames and manual clones.
+#[derive(Debug)]
+pub struct Duplicate {
+    pub url: String,
+    pub local_repos: Vec<LocalRepo>,
+}
+
+pub fn find_duplicates(local_repos: &Vec<LocalRepo>) -> Vec<Duplicate> {
+    let mut by_url: BTreeMap<String, Vec<LocalRepo>> = BTreeMap::new();
+    for local_repo in local_repos {
+        let Some(url) = git::remote_url(&local_repo.path) else {
+            continue;
+        };
+        by_url
+            .entry(git::normalize_url(&url))
+            .or_default()
+            .push(local_repo.clone());
+    }
+    by_url
+        .into_iter()
+        .filter(|(_, local_repos)| local_repos.len() > 1)
+        .map(|(url, local_repos)| Duplicate { url, local_repos })
+        .collect()
+}
+
+// asks which clone to keep and moves the others into the archive dir.
+pub fn resolve(duplicate: &Duplicate) {
+    let names: Vec<&str> = duplicate
+        .local_repos
+        .iter()
+        .map(|local_repo| local_repo.name.as_str())
+        .collect();
+    let Some(keep) = prompt::choose(
+        &format!("which clone of {} to keep?", duplicate.url),
+        &names,
+    ) else {
+        return;
+    };
+    for (index, local_repo) in duplicate.local_repos.iter().enumerate() {
+        if index == keep {
+            continue;
+        }
+        match archive::apply_action(local_repo, "move") {
+            Ok(message) => println!(
+                "{}: {}",
+                style::paint(&local_repo.name, style::WARNING),
+                message
+            ),
+            Err(message) => println!(
+                "{}: failed to archive duplicate: {}",
+                style::paint(&local_repo.name, style::ERROR),
+                message
+            ),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+// parses durations like "90s", "30m", "24h", "7d", "2w", "6mo" or "1y" for clap value parsers.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|character: char| !character.is_ascii_digit())
+        .ok_or(format!("missing unit in '{}', e.g. 24h or 7d", value))?;
+    let (amount, unit) = value.split_at(unit_start);
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| format!("invalid amount in '{}', e.g. 24h or 7d", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "mo" => 30 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}', use one of s, m, h, d, w, mo, y",
+                unit
+            ));
+        }
+    };
+    Ok(Duration::from_secs(amount * seconds))
+}
@@ -0,0 +1,80 @@
+use crate::config;
+use crate::failure_log;
+use crate::failure_log::FailedRepo;
+
+// e.g. reposync: 2 failed, 3 updated, 1 cloned. up-to-date repos only when nothing else happened.
+pub fn subject(counts: &[(&str, usize)]) -> String {
+    let parts: Vec<String> = counts
+        .iter()
+        .filter(|(label, count)| *count > 0 && *label != "up to date")
+        .map(|(label, count)| format!("{} {}", count, label))
+        .collect();
+    if parts.is_empty() {
+        return "reposync: all repos up to date".to_string();
+    }
+    format!("reposync: {}", parts.join(", "))
+}
+
+// plain text, the failed repos in the same Markdown list as --copy-failures.
+pub fn body(counts: &[(&str, usize)], failed_repos: &[FailedRepo]) -> String {
+    let mut body = String::new();
+    for (label, count) in counts.iter().filter(|(_, count)| *count > 0) {
+        body.push_str(&format!("{}: {}\n", label, count));
+    }
+    if !failed_repos.is_empty() {
+        body.push('\n');
+        body.push_str(&failure_log::markdown_summary(failed_repos));
+    }
+    body
+}
+
+#[cfg(feature = "email")]
+pub fn send(settings: &config::Email, subject: &str, body: &str) -> Result<(), String> {
+    use lettre::Message;
+    use lettre::SmtpTransport;
+    use lettre::Transport;
+    use lettre::message::Mailbox;
+    use lettre::transport::smtp::authentication::Credentials;
+
+    let mailbox = |address: &str| {
+        address
+            .parse::<Mailbox>()
+            .map_err(|e| format!("{}: {}", address, e))
+    };
+    let mut message = Message::builder()
+        .from(mailbox(&settings.from)?)
+        .subject(subject);
+    for to in &settings.to {
+        message = message.to(mailbox(to)?);
+    }
+    let message = message.body(body.to_string()).map_err(|e| e.to_string())?;
+    // 465 is implicit TLS, every other port starts in plain text and upgrades.
+    let mut transport = match settings.smtp_port {
+        Some(465) => SmtpTransport::relay(&settings.smtp_host),
+        _ => SmtpTransport::starttls_relay(&settings.smtp_host),
+    }
+    .map_err(|e| format!("{}: {}", settings.smtp_host, e))?;
+    if let Some(port) = settings.smtp_port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = &settings.username {
+        let password = match &settings.password_command {
+            Some(password_command) => config::token_from_command(password_command)?,
+            None => String::new(),
+        };
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+    transport
+        .build()
+        .send(&message)
+        .map_err(|e| format!("{}: {}", settings.smtp_host, e))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "email"))]
+pub fn send(_settings: &config::Email, _subject: &str, _body: &str) -> Result<(), String> {
+    Err(
+        "reposync was built without the email feature, e.g. cargo install --features email"
+            .to_string(),
+    )
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ArgMatches;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::paths;
+use crate::style;
+use crate::time_format;
+
+// one line of the append-only feed in events.jsonl.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub time: String,
+    pub repo: String,
+    // updated, cloned or archived.
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<String>,
+}
+
+pub fn events_file() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("events.jsonl"))
+}
+
+// best effort, a full disk must not fail the sync. each event is a single append, so
+// concurrent tasks don't interleave their lines.
+pub fn append(repo: &str, kind: &str, commits: Vec<String>) {
+    let Some(path) = events_file() else {
+        return;
+    };
+    let event = Event {
+        time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        repo: repo.to_string(),
+        kind: kind.to_string(),
+        commits,
+    };
+    let Ok(json) = serde_json::to_string(&event) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = file.write_all(format!("{}\n", json).as_bytes());
+    }
+}
+
+// * matches any run of characters, everything else literally.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|index| name.is_char_boundary(*index))
+                .any(|index| matches_pattern(rest, &name[index..]))
+        }
+    }
+}
+
+fn print_event(line: &str, patterns: &Vec<String>) {
+    let Ok(event) = serde_json::from_str::<Event>(line) else {
+        return;
+    };
+    if !patterns.is_empty()
+        && !patterns
+            .iter()
+            .any(|pattern| matches_pattern(pattern, &event.repo))
+    {
+        return;
+    }
+    println!(
+        "{} {}: {}",
+        time_format::timestamp_str(&event.time),
+        style::paint(
+            &event.repo,
+            match event.kind.as_str() {
+                "updated" => style::UPDATED,
+                "cloned" => style::CLONED,
+                "archived" => style::ARCHIVED,
+                _ => style::WARNING,
+            }
+        ),
+        event.kind
+    );
+    for commit in &event.commits {
+        println!("  {}", commit);
+    }
+}
+
+// prints the feed and with follow keeps printing new events as syncs append them.
+async fn print_feed(patterns: &Vec<String>, follow: bool) -> Result<(), String> {
+    let path = events_file().ok_or("no state directory, neither HOME nor XDG_STATE_HOME is set")?;
+    let mut offset = 0;
+    let mut pending = String::new();
+    loop {
+        match fs::File::open(&path) {
+            Ok(mut file) => {
+                let length = file.metadata().map_err(|e| e.to_string())?.len();
+                // the feed was truncated or replaced, start over.
+                if length < offset {
+                    offset = 0;
+                    pending.clear();
+                }
+                file.seek(SeekFrom::Start(offset))
+                    .map_err(|e| e.to_string())?;
+                let mut new_content = String::new();
+                offset += file
+                    .read_to_string(&mut new_content)
+                    .map_err(|e| e.to_string())? as u64;
+                pending.push_str(&new_content);
+                // a line that is still being written is kept until its newline arrives.
+                while let Some((line, rest)) = pending.split_once('\n') {
+                    print_event(line, patterns);
+                    pending = rest.to_string();
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if !follow {
+                    println!("no events yet, they are recorded by syncs with --events");
+                }
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+        if !follow {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+pub async fn run(events_args: &ArgMatches) {
+    let patterns: Vec<String> = events_args
+        .get_many::<String>("repo")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    if let Err(e) = print_feed(&patterns, events_args.get_flag("follow")).await {
+        println!(
+            "{}: {}",
+            style::paint("failed to read events", style::ERROR),
+            e
+        );
+        std::process::exit(1);
+    }
+}
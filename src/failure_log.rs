@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::health;
+use crate::paths;
+
+pub struct FailedRepo<'a> {
+    pub name: &'a str,
+    pub operation: &'a str,
+    pub output: &'a str,
+}
+
+// writes one <repo>.log per failed repo into a new timestamped directory.
+pub fn write_failure_logs(failed_repos: &[FailedRepo]) -> Result<PathBuf, std::io::Error> {
+    let state_dir = paths::state_dir().ok_or(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "no state directory, neither HOME nor XDG_STATE_HOME is set",
+    ))?;
+    let log_dir = state_dir
+        .join("logs")
+        .join(chrono::Local::now().format("%Y-%m-%dT%H-%M-%S").to_string());
+    fs::create_dir_all(&log_dir)?;
+    for failed_repo in failed_repos {
+        fs::write(
+            log_dir.join(format!("{}.log", failed_repo.name)),
+            format!(
+                "{} failed:\n{}\n",
+                failed_repo.operation, failed_repo.output
+            ),
+        )?;
+    }
+    return Ok(log_dir);
+}
+
+// the fatal: or error: line of git says the most, the first line otherwise.
+pub fn excerpt(output: &str) -> String {
+    match output
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+    {
+        Some(line) => health::error_excerpt(line),
+        None => health::error_excerpt(output),
+    }
+}
+
+// short enough to paste into the team chat, the failure logs have the full output.
+pub fn markdown_summary(failed_repos: &[FailedRepo]) -> String {
+    let mut summary = format!("**reposync: {} repos failed**\n", failed_repos.len());
+    for failed_repo in failed_repos {
+        summary.push_str(&format!(
+            "- `{}` ({}): {}\n",
+            failed_repo.name,
+            failed_repo.operation,
+            excerpt(failed_repo.output)
+        ));
+    }
+    summary.push_str(&format!("\nretry: `{}`\n", rerun_command(failed_repos)));
+    summary
+}
+
+pub fn rerun_command(failed_repos: &[FailedRepo]) -> String {
+    let mut command = String::from("reposync sync");
+    for failed_repo in failed_repos {
+        command.push(' ');
+        command.push_str(failed_repo.name);
+    }
+    command
+}
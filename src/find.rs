@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git::list_local_repos;
+use crate::queue::WorkQueue;
+use crate::style;
+
+async fn contains_all(repo_path: &PathBuf, files: &[String]) -> bool {
+    for file in files {
+        if !tokio::fs::try_exists(repo_path.join(file))
+            .await
+            .unwrap_or(false)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+async fn contains_any(repo_path: &PathBuf, files: &[String]) -> bool {
+    for file in files {
+        if tokio::fs::try_exists(repo_path.join(file))
+            .await
+            .unwrap_or(false)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+// repos that contain every --file and none of the --not-file paths, relative to the repo root.
+async fn find_repos(repo_root_dir: &PathBuf, find_args: &ArgMatches, jobs: usize) -> Vec<String> {
+    let files: Vec<String> = find_args
+        .get_many::<String>("file")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let not_files: Vec<String> = find_args
+        .get_many::<String>("not_file")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let mut work_queue = WorkQueue::new(jobs);
+    for local_repo in list_local_repos(repo_root_dir) {
+        let files = files.clone();
+        let not_files = not_files.clone();
+        work_queue.push(async move {
+            let matches = contains_all(&local_repo.path, &files).await
+                && !contains_any(&local_repo.path, &not_files).await;
+            (local_repo.name, matches)
+        });
+    }
+    let mut found: Vec<String> = Vec::new();
+    while let Some((name, matches)) = work_queue.next().await {
+        if matches {
+            found.push(name);
+        }
+    }
+    found.sort();
+    found
+}
+
+pub async fn run(repo_root_dir: &PathBuf, find_args: &ArgMatches, jobs: usize) {
+    let found = find_repos(repo_root_dir, find_args, jobs).await;
+    for name in &found {
+        println!("{}", name);
+    }
+    println!(
+        "{}: {}",
+        style::paint("Matching repos", style::OK),
+        found.len()
+    );
+}
@@ -1,12 +1,23 @@
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs;
-use std::{path::PathBuf, process::Command};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
-use reqwest::Client;
-use reqwest::Url;
-use reqwest::header::ACCEPT;
-use reqwest::header::USER_AGENT;
 use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::children;
+use crate::read_only;
+use crate::rules;
+use crate::rules::Category;
 
 #[derive(Debug, Clone)]
 pub struct LocalRepo {
@@ -14,42 +25,1185 @@ pub struct LocalRepo {
     pub path: PathBuf,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug)]
+pub struct CloneProgress {
+    pub phase: String,
+    pub percent: u64,
+    // received so far, only known while objects are received.
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct RemoteRepo {
     pub name: String,
     pub archived: bool,
     pub ssh_url: String,
+    pub clone_url: String,
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub pushed_at: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub license: Option<License>,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    // in KiB, as reported by the API.
+    #[serde(default)]
+    pub size: Option<u64>,
+    // set for repos of the sources in the config file, which strip their own prefix.
+    #[serde(skip)]
+    pub dir_name: Option<String>,
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+// as detected by GitHub, spdx_id is NOASSERTION for licenses it doesn't recognize.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct License {
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl RemoteRepo {
+    pub fn has_language(&self, language: &str) -> bool {
+        self.language
+            .as_ref()
+            .is_some_and(|repo_language| repo_language.eq_ignore_ascii_case(language))
+    }
+
+    // owner/repo for API calls, taken from the clone url as providers only have to send urls.
+    pub fn full_name(&self) -> Option<String> {
+        let path = normalize_url(&self.clone_url);
+        let (_host, full_name) = path.split_once('/')?;
+        Some(full_name.to_string())
+    }
+
+    pub fn url(&self, protocol: &str) -> &String {
+        match protocol {
+            "https" => &self.clone_url,
+            _ => &self.ssh_url,
+        }
+    }
+}
+
+fn git_binary() -> &'static PathBuf {
+    static GIT_BINARY: OnceLock<PathBuf> = OnceLock::new();
+    GIT_BINARY.get_or_init(find_git_binary)
+}
+
+fn find_git_binary() -> PathBuf {
+    if !cfg!(windows) {
+        return PathBuf::from("git");
+    }
+    if let Some(paths) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&paths) {
+            let candidate = dir.join("git.exe");
+            if candidate.is_file() {
+                return candidate;
+            }
+        }
+    }
+    if let Some(install_path) = git_for_windows_install_path() {
+        let candidate = install_path.join("cmd").join("git.exe");
+        if candidate.is_file() {
+            return candidate;
+        }
+    }
+    PathBuf::from("git.exe")
+}
+
+fn git_for_windows_install_path() -> Option<PathBuf> {
+    for key in [
+        "HKLM\\SOFTWARE\\GitForWindows",
+        "HKCU\\SOFTWARE\\GitForWindows",
+    ] {
+        let Ok(output) = Command::new("reg")
+            .args(["query", key, "/v", "InstallPath"])
+            .output()
+        else {
+            continue;
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for line in stdout.lines() {
+            if let Some((_, install_path)) = line.split_once("REG_SZ") {
+                return Some(PathBuf::from(install_path.trim()));
+            }
+        }
+    }
+    None
+}
+
+static TOKEN_CREDENTIALS: OnceLock<String> = OnceLock::new();
+static REMOTE_NAMES: OnceLock<(String, BTreeMap<String, String>)> = OnceLock::new();
+static SSH_COMMAND: OnceLock<String> = OnceLock::new();
+static NO_TERMINAL_PROMPT: AtomicBool = AtomicBool::new(false);
+
+// answers https credential requests with the given token without storing it anywhere.
+pub fn use_token_credentials(token: &str) {
+    let _ = TOKEN_CREDENTIALS.set(token.to_string());
+    disable_terminal_prompt();
+}
+
+// the remote of the team repo, by directory name with a default for all other clones.
+pub fn set_remote_names(default: &str, by_dir: BTreeMap<String, String>) {
+    let _ = REMOTE_NAMES.set((default.to_string(), by_dir));
+}
+
+// origin unless the config names another remote for the clone at path.
+pub fn remote_name(path: &Path) -> &'static str {
+    let Some((default, by_dir)) = REMOTE_NAMES.get() else {
+        return "origin";
+    };
+    path.file_name()
+        .and_then(|dir_name| by_dir.get(&*dir_name.to_string_lossy()))
+        .unwrap_or(default)
+}
+
+// e.g. main for origin/main, None for refs of other remotes.
+pub fn remote_branch<'a>(path: &Path, remote_ref: &'a str) -> Option<&'a str> {
+    remote_ref
+        .strip_prefix(remote_name(path))?
+        .strip_prefix('/')
+}
+
+// e.g. to share the connections of ssh::Multiplex.
+pub fn use_ssh_command(ssh_command: &str) {
+    let _ = SSH_COMMAND.set(ssh_command.to_string());
+}
+
+// makes https operations fail instead of waiting for a username nobody will type.
+pub fn disable_terminal_prompt() {
+    NO_TERMINAL_PROMPT.store(true, Ordering::Relaxed);
+}
+
+pub fn git_command() -> Command {
+    let mut command = Command::new(git_binary());
+    if cfg!(windows) {
+        // team repos regularly exceed MAX_PATH once nested under the root dir.
+        command.arg("-c").arg("core.longpaths=true");
+    }
+    if let Some(token) = TOKEN_CREDENTIALS.get() {
+        command
+            .arg("-c")
+            .arg("credential.helper=")
+            .arg("-c")
+            .arg("credential.helper=!f() { echo username=x-access-token; echo \"password=$REPOSYNC_GIT_TOKEN\"; }; f")
+            .env("REPOSYNC_GIT_TOKEN", token);
+    }
+    if let Some(ssh_command) = SSH_COMMAND.get() {
+        command.env("GIT_SSH_COMMAND", ssh_command);
+    }
+    if NO_TERMINAL_PROMPT.load(Ordering::Relaxed) {
+        command.env("GIT_TERMINAL_PROMPT", "0");
+    }
+    // git status would otherwise refresh the index of the repos it looks at.
+    if read_only::is_read_only() {
+        command.env("GIT_OPTIONAL_LOCKS", "0");
+    }
+    command
+}
+
+// runs git without holding a runtime thread, git is killed when the task is dropped.
+async fn output(mut command: Command) -> Result<std::process::Output, std::io::Error> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let (child, _tracked) = children::spawn(command)?;
+    child.wait_with_output().await
+}
+
+// e.g. git version 2.45.1.
+pub fn git_version() -> Option<String> {
+    let output = git_command().arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+pub fn credential_helper() -> Option<String> {
+    let output = git_command()
+        .arg("config")
+        .arg("--get")
+        .arg("credential.helper")
+        .output()
+        .ok()?;
+    let helper = String::from_utf8_lossy(output.stdout.trim_ascii()).to_string();
+    if !output.status.success() || helper.is_empty() {
+        return None;
+    }
+    Some(helper)
+}
+
+pub fn set_global_credential_helper(helper: &str) -> Result<std::process::Output, std::io::Error> {
+    return git_command()
+        .arg("config")
+        .arg("--global")
+        .arg("credential.helper")
+        .arg(helper)
+        .output();
+}
+
+// parses git's progress lines, e.g. "Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s".
+// the server's progress comes prefixed with remote:.
+pub fn parse_progress_line(line: &str) -> Option<CloneProgress> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(':')?;
+    let (percent, rest) = rest.split_once('%')?;
+    let percent = percent.trim().parse::<u64>().ok()?;
+    let phase = phase.trim().to_string();
+    let bytes = if phase == "Receiving objects" || phase == "Unpacking objects" {
+        rest.split_once("),")
+            .and_then(|(_, size)| size.split(['|', ',']).next())
+            .and_then(parse_size)
+    } else {
+        None
+    };
+    Some(CloneProgress {
+        phase,
+        percent,
+        bytes,
+    })
+}
+
+// sizes as git prints them, e.g. 512 bytes, 12.00 KiB or 1.20 MiB.
+fn parse_size(size: &str) -> Option<u64> {
+    let (amount, unit) = size.trim().split_once(' ')?;
+    let amount = amount.parse::<f64>().ok()?;
+    let factor = match unit.trim() {
+        "bytes" | "byte" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((amount * factor) as u64)
+}
+
+// e.g. 1.2 MiB, the way git prints sizes.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// runs git with its progress on stderr, which is separated by \r. progress updates go to
+// on_progress and everything else is kept as the regular stderr. stdout is read at the same
+// time, so a long merge summary can't block git.
+async fn output_with_progress(
+    mut command: Command,
+    mut on_progress: impl FnMut(CloneProgress),
+) -> Result<std::process::Output, std::io::Error> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let (mut child, _tracked) = children::spawn(command)?;
+    let stdout_pipe = child.stdout.take();
+    let stderr_pipe = child.stderr.take();
+    let read_stdout = async {
+        let mut stdout: Vec<u8> = Vec::new();
+        if let Some(mut pipe) = stdout_pipe {
+            pipe.read_to_end(&mut stdout).await?;
+        }
+        Ok::<Vec<u8>, std::io::Error>(stdout)
+    };
+    let read_stderr = async {
+        let mut stderr: Vec<u8> = Vec::new();
+        if let Some(mut pipe) = stderr_pipe {
+            let mut buffer = [0u8; 4096];
+            let mut line: Vec<u8> = Vec::new();
+            loop {
+                let read = pipe.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                for byte in &buffer[..read] {
+                    if *byte != b'\r' && *byte != b'\n' {
+                        line.push(*byte);
+                        continue;
+                    }
+                    match parse_progress_line(&String::from_utf8_lossy(&line)) {
+                        Some(progress) => on_progress(progress),
+                        None if !line.is_empty() => {
+                            stderr.extend_from_slice(&line);
+                            stderr.push(b'\n');
+                        }
+                        None => (),
+                    }
+                    line.clear();
+                }
+            }
+            stderr.extend_from_slice(&line);
+        }
+        Ok::<Vec<u8>, std::io::Error>(stderr)
+    };
+    let (stdout, stderr) = tokio::try_join!(read_stdout, read_stderr)?;
+    let status = child.wait().await?;
+    Ok(std::process::Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+// new repos are cloned into .<name>.reposync-tmp and only renamed into place on success.
+pub const CLONE_TMP_SUFFIX: &str = ".reposync-tmp";
+
+// removes the directory of a clone that didn't succeed, also when the clone is cancelled or times out.
+struct PartialClone {
+    path: PathBuf,
+    keep: bool,
 }
 
-pub fn git_clone(
+impl Drop for PartialClone {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+pub async fn git_clone(
     remote_repo: &RemoteRepo,
     repo_root_dir: PathBuf,
-    github_team_prefix: String,
+    dir_name: String,
+    clone_args: &[String],
+    protocol: &str,
+    mut on_progress: impl FnMut(CloneProgress),
+) -> Result<std::process::Output, std::io::Error> {
+    let clone_path = repo_root_dir.join(&dir_name);
+    if clone_path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", clone_path.display()),
+        ));
+    }
+    let tmp_dir = format!(".{}{}", dir_name, CLONE_TMP_SUFFIX);
+    let tmp_path = repo_root_dir.join(&tmp_dir);
+    // left over by an interrupted run, the clone simply starts over.
+    if tmp_path.exists() {
+        fs::remove_dir_all(&tmp_path)?;
+    }
+    let mut partial_clone = PartialClone {
+        path: tmp_path.clone(),
+        keep: false,
+    };
+    let clone_command = |clone_args: &[String]| {
+        let mut command = git_command();
+        command
+            .arg("clone")
+            .arg("--progress")
+            .arg("--origin")
+            .arg(remote_name(&clone_path));
+        if cfg!(windows) {
+            command.arg("--config").arg("core.longpaths=true");
+        }
+        command
+            .args(clone_args)
+            .arg(remote_repo.url(protocol))
+            .arg(&tmp_dir)
+            .current_dir(&repo_root_dir);
+        command
+    };
+    let mut output = output_with_progress(clone_command(clone_args), &mut on_progress).await?;
+    // a repo without commits since the cut has no history to cut, it gets its last commit.
+    if !output.status.success()
+        && String::from_utf8_lossy(&output.stderr).contains("shallow info")
+        && clone_args
+            .iter()
+            .any(|arg| arg.starts_with("--shallow-since"))
+    {
+        if tmp_path.exists() {
+            fs::remove_dir_all(&tmp_path)?;
+        }
+        let clone_args: Vec<String> = clone_args
+            .iter()
+            .map(|arg| {
+                if arg.starts_with("--shallow-since") {
+                    "--depth=1".to_string()
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect();
+        output = output_with_progress(clone_command(&clone_args), &mut on_progress).await?;
+    }
+    if output.status.success() {
+        fs::rename(&tmp_path, &clone_path)?;
+        partial_clone.keep = true;
+    }
+    return Ok(output);
+}
+
+// a mirror of the repo in the object cache, e.g. name.git from git clone --mirror.
+pub fn reference_repo(
+    reference_dir: &PathBuf,
+    remote_repo: &RemoteRepo,
+    dir_name: &str,
+) -> Option<PathBuf> {
+    [
+        format!("{}.git", remote_repo.name),
+        remote_repo.name.clone(),
+        dir_name.to_string(),
+    ]
+    .iter()
+    .map(|name| reference_dir.join(name))
+    .find(|path| path.is_dir())
+}
+
+// clones a mirror for the object cache or fetches into an existing one, a mirror fetch
+// replaces all refs with the ones of the remote. an automatic gc could drop objects that
+// reference clones still need, only reposync cache gc compacts a mirror.
+pub async fn git_mirror(
+    remote_repo: &RemoteRepo,
+    mirror_path: &PathBuf,
+    protocol: &str,
+) -> Result<std::process::Output, std::io::Error> {
+    let mut command = git_command();
+    command.arg("-c").arg("gc.auto=0");
+    if mirror_path.exists() {
+        command
+            .arg("fetch")
+            .arg("--prune")
+            .arg("--quiet")
+            .current_dir(mirror_path);
+    } else {
+        command
+            .arg("clone")
+            .arg("--mirror")
+            .arg("--quiet")
+            .arg("--config")
+            .arg("gc.auto=0")
+            .arg(remote_repo.url(protocol))
+            .arg(mirror_path);
+    }
+    output(command).await
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PullOptions {
+    pub prune: bool,
+    pub prune_tags: bool,
+    // fetches the full history of a shallow clone when a pull needs older commits.
+    pub deepen_on_demand: bool,
+}
+
+// a merge base or older commits beyond the cut of a shallow clone.
+fn needs_more_history(stderr: &str) -> bool {
+    stderr.contains("unrelated histories")
+        || stderr.contains("shallow")
+        || stderr.contains("no merge base")
+}
+
+// --progress makes git report the received size also when stderr is not a terminal.
+pub async fn git_pull(
+    local_repo: LocalRepo,
+    pull_options: &PullOptions,
+    mut on_progress: impl FnMut(CloneProgress),
 ) -> Result<std::process::Output, std::io::Error> {
-    let dir_without_prefix = remote_repo.name.replace(github_team_prefix.as_str(), "");
-    return Command::new("git")
+    // without a remote git pulls the upstream of the branch, which is origin for most
+    // clones. a configured remote is pulled into its default branch, other branches only
+    // when they track it.
+    let remote_name = remote_name(&local_repo.path);
+    let mut remote_args: Vec<String> = Vec::new();
+    if remote_name != "origin" {
+        remote_args.push(remote_name.to_string());
+        let remote_ref =
+            parse_default_branch_ref(output(default_branch_ref_command(&local_repo.path)).await);
+        let mut command = git_command();
+        command
+            .arg("symbolic-ref")
+            .arg("--quiet")
+            .arg("--short")
+            .arg("HEAD")
+            .current_dir(&local_repo.path);
+        if let Some(default_branch) = remote_branch(&local_repo.path, &remote_ref)
+            && let Ok(output) = output(command).await
+            && output.stdout.trim_ascii() == default_branch.as_bytes()
+        {
+            remote_args.push(default_branch.to_string());
+        }
+    }
+    let pull_command = || {
+        let mut command = git_command();
+        if pull_options.prune_tags {
+            // git pull has no --prune-tags, the fetch it runs picks it up from the config.
+            command.arg("-c").arg("fetch.pruneTags=true");
+        }
+        command.arg("pull").arg("--progress");
+        if pull_options.prune || pull_options.prune_tags {
+            command.arg("--prune");
+        }
+        command.args(&remote_args).current_dir(&local_repo.path);
+        command
+    };
+    let output = output_with_progress(pull_command(), &mut on_progress).await?;
+    if output.status.success()
+        || !pull_options.deepen_on_demand
+        || !local_repo.path.join(".git").join("shallow").exists()
+        || !needs_more_history(&String::from_utf8_lossy(&output.stderr))
+    {
+        return Ok(output);
+    }
+    let mut unshallow = git_command();
+    unshallow
+        .arg("fetch")
+        .arg("--progress")
+        .arg("--unshallow")
+        .current_dir(&local_repo.path);
+    let unshallow_output = output_with_progress(unshallow, &mut on_progress).await?;
+    if !unshallow_output.status.success() {
+        return Ok(unshallow_output);
+    }
+    output_with_progress(pull_command(), &mut on_progress).await
+}
+
+pub async fn verify_head_signature(
+    path: &PathBuf,
+    allowed_signers: Option<&PathBuf>,
+) -> Result<(), String> {
+    let mut command = git_command();
+    if let Some(allowed_signers) = allowed_signers {
+        command.arg("-c").arg(format!(
+            "gpg.ssh.allowedSignersFile={}",
+            allowed_signers.display()
+        ));
+    }
+    command.arg("verify-commit").arg("HEAD").current_dir(path);
+    let output = output(command).await.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let message = String::from_utf8_lossy(output.stderr.trim_ascii()).to_string();
+    if message.is_empty() {
+        return Err("HEAD is not signed".to_string());
+    }
+    Err(message)
+}
+
+pub fn git_clone_into(url: &str, target: &PathBuf) -> Result<std::process::Output, std::io::Error> {
+    let mut command = git_command();
+    command
         .arg("clone")
-        .arg(remote_repo.ssh_url.clone())
-        .arg(dir_without_prefix)
-        .current_dir(repo_root_dir)
-        .output();
+        .arg("--origin")
+        .arg(remote_name(target));
+    if cfg!(windows) {
+        command.arg("--config").arg("core.longpaths=true");
+    }
+    return command.arg(url).arg(target).output();
+}
+
+pub fn remote_url(path: &PathBuf) -> Option<String> {
+    let output = git_command()
+        .arg("remote")
+        .arg("get-url")
+        .arg(remote_name(path))
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// modified and untracked files relative to the repo, deleted files are left out.
+pub fn dirty_files(path: &PathBuf) -> Option<Vec<String>> {
+    let output = git_command()
+        .arg("status")
+        .arg("--porcelain")
+        .arg("-z")
+        .arg("--untracked-files=all")
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let mut entries = stdout.split('\0').filter(|entry| entry.len() > 3);
+    let mut files: Vec<String> = Vec::new();
+    while let Some(entry) = entries.next() {
+        let (status, file) = entry.split_at(3);
+        if status.starts_with('R') || status.starts_with('C') {
+            // renames and copies are followed by their source path.
+            entries.next();
+        }
+        if !status.contains('D') {
+            files.push(file.to_string());
+        }
+    }
+    Some(files)
+}
+
+// commits on any local branch or a detached HEAD that are on no remote branch.
+pub fn unpushed_commit_count(path: &PathBuf) -> Option<u64> {
+    let output = git_command()
+        .arg("rev-list")
+        .arg("--count")
+        .arg("--branches")
+        .arg("HEAD")
+        .arg("--not")
+        .arg("--remotes")
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(output.stdout.trim_ascii())
+        .parse::<u64>()
+        .ok()
+}
+
+pub fn stash_count(path: &PathBuf) -> Option<usize> {
+    let output = git_command()
+        .arg("stash")
+        .arg("list")
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).lines().count())
+}
+
+// None on a detached HEAD.
+pub fn current_branch(path: &PathBuf) -> Option<String> {
+    let output = git_command()
+        .arg("symbolic-ref")
+        .arg("--quiet")
+        .arg("--short")
+        .arg("HEAD")
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// commits on HEAD that are not on its upstream, None without an upstream.
+pub fn ahead_of_upstream(path: &PathBuf) -> Option<u64> {
+    let output = git_command()
+        .arg("rev-list")
+        .arg("--count")
+        .arg("@{upstream}..HEAD")
+        .current_dir(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(output.stdout.trim_ascii())
+        .parse::<u64>()
+        .ok()
 }
 
-pub fn git_pull(local_repo: LocalRepo) -> Result<std::process::Output, std::io::Error> {
-    return Command::new("git")
-        .arg("pull")
-        .current_dir(local_repo.path)
+pub fn git_push(path: &PathBuf) -> Result<std::process::Output, std::io::Error> {
+    return git_command().arg("push").current_dir(path).output();
+}
+
+pub fn is_up_to_date(stdout: &str) -> bool {
+    if let Some(category) = rules::classify(stdout) {
+        return category == Category::UpToDate;
+    }
+    stdout == "Already up to date" || stdout == "Already up to date."
+}
+
+pub fn is_credential_error(stderr: &str) -> bool {
+    if let Some(category) = rules::classify(stderr) {
+        return category == Category::Credential;
+    }
+    const CREDENTIAL_ERRORS: [&str; 5] = [
+        "could not read Username",
+        "could not read Password",
+        "terminal prompts disabled",
+        "Invalid username or password",
+        "Authentication failed for",
+    ];
+    CREDENTIAL_ERRORS
+        .iter()
+        .any(|credential_error| stderr.contains(credential_error))
+}
+
+// "detected dubious ownership" since git 2.36, "unsafe repository" in 2.35.2.
+pub fn is_unsafe_directory_error(stderr: &str) -> bool {
+    if let Some(category) = rules::classify(stderr) {
+        return category == Category::UnsafeDirectory;
+    }
+    stderr.contains("detected dubious ownership") || stderr.contains("unsafe repository")
+}
+
+// git's own check, compared before git refuses the repo.
+#[cfg(unix)]
+pub fn is_owned_by_other_user(path: &PathBuf) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.uid() != unsafe { libc::geteuid() })
+}
+
+#[cfg(not(unix))]
+pub fn is_owned_by_other_user(_path: &PathBuf) -> bool {
+    // ownership on Windows is not a plain uid, git's refusal is still reported per repo.
+    false
+}
+
+pub fn safe_directories() -> Vec<String> {
+    git_command()
+        .arg("config")
+        .arg("--global")
+        .arg("--get-all")
+        .arg("safe.directory")
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// one entry per repo instead of *, so only clones in the repo root dir are trusted.
+pub fn add_safe_directory(path: &PathBuf) -> Result<std::process::Output, std::io::Error> {
+    git_command()
+        .arg("config")
+        .arg("--global")
+        .arg("--add")
+        .arg("safe.directory")
+        .arg(path)
+        .output()
+}
+
+pub fn is_corruption_error(stderr: &str) -> bool {
+    if let Some(category) = rules::classify(stderr) {
+        return category == Category::Corrupt;
+    }
+    const CORRUPTION_ERRORS: [&str; 7] = [
+        "bad object",
+        "is corrupt",
+        "broken link",
+        "bad ref",
+        "unable to read tree",
+        "invalid sha1 pointer",
+        "did not send all necessary objects",
+    ];
+    // e.g. object file .git/objects/3f/9a... is empty, left behind by a crash during a write.
+    // a bare "is empty" also matches messages like "repository is empty".
+    let has_empty_object = stderr
+        .lines()
+        .any(|line| line.contains("object file ") && line.trim_end().ends_with(" is empty"));
+    has_empty_object
+        || CORRUPTION_ERRORS
+            .iter()
+            .any(|corruption_error| stderr.contains(corruption_error))
+}
+
+pub async fn git_fsck(path: &PathBuf) -> Result<(), String> {
+    let mut command = git_command();
+    command.arg("fsck").arg("--no-dangling").current_dir(path);
+    let output = output(command).await.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}\n{}",
+        String::from_utf8_lossy(output.stderr.trim_ascii()),
+        String::from_utf8_lossy(output.stdout.trim_ascii())
+    )
+    .trim()
+    .to_string())
+}
+
+// the commit HEAD of origin points to, a single round trip without fetching objects.
+pub async fn git_remote_head(path: &PathBuf) -> Option<String> {
+    let mut command = git_command();
+    command
+        .arg("ls-remote")
+        .arg(remote_name(path))
+        .arg("HEAD")
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(|commit| commit.to_string())
+}
+
+pub async fn git_head(path: &PathBuf) -> Option<String> {
+    let mut command = git_command();
+    command.arg("rev-parse").arg("HEAD").current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// exits with 1 when nothing matched, like grep.
+pub async fn git_grep(
+    path: &PathBuf,
+    pattern: &str,
+    ignore_case: bool,
+    fixed_strings: bool,
+) -> Result<std::process::Output, std::io::Error> {
+    let mut command = git_command();
+    command
+        .arg("grep")
+        .arg("--line-number")
+        .arg("-I")
+        .arg("--no-color")
+        .arg("--full-name");
+    if ignore_case {
+        command.arg("--ignore-case");
+    }
+    if fixed_strings {
+        command.arg("--fixed-strings");
+    }
+    command.arg("-e").arg(pattern).current_dir(path);
+    output(command).await
+}
+
+// short hash and subject of each commit, newest first.
+pub async fn git_log_range(path: &PathBuf, from: &str, to: &str) -> Option<Vec<String>> {
+    let mut command = git_command();
+    command
+        .arg("log")
+        .arg("--format=%h %s")
+        .arg(format!("{}..{}", from, to))
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.to_string())
+            .collect(),
+    )
+}
+
+pub async fn git_diffstat(
+    path: &PathBuf,
+    old_head: &str,
+    new_head: &str,
+    color: bool,
+) -> Option<String> {
+    let mut command = git_command();
+    command
+        .arg("diff")
+        .arg("--stat")
+        .arg(if color {
+            "--color=always"
+        } else {
+            "--color=never"
+        })
+        .arg(format!("{}..{}", old_head, new_head))
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii_end()).to_string())
+}
+
+fn default_branch_ref_command(path: &PathBuf) -> Command {
+    let mut command = git_command();
+    command
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg(format!("{}/HEAD", remote_name(path)))
+        .current_dir(path);
+    command
+}
+
+// e.g. "origin/main", falls back to HEAD for repos without a remote HEAD.
+pub fn default_branch_ref(path: &PathBuf) -> String {
+    parse_default_branch_ref(default_branch_ref_command(path).output())
+}
+
+fn parse_default_branch_ref(output: Result<std::process::Output, std::io::Error>) -> String {
+    match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(output.stdout.trim_ascii()).to_string()
+        }
+        _ => "HEAD".to_string(),
+    }
+}
+
+// commits on the local default branch that are not on the remote one, e.g. an accidental commit to main.
+pub async fn local_commits_on_default(path: &PathBuf) -> Option<(String, u64)> {
+    let remote_ref = parse_default_branch_ref(output(default_branch_ref_command(path)).await);
+    let local_branch = remote_branch(path, &remote_ref)?.to_string();
+    let mut command = git_command();
+    command
+        .arg("rev-list")
+        .arg("--count")
+        .arg(format!("{}..{}", remote_ref, local_branch))
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let count = String::from_utf8_lossy(output.stdout.trim_ascii())
+        .parse::<u64>()
+        .ok()?;
+    Some((local_branch, count))
+}
+
+pub async fn git_current_branch(path: &PathBuf) -> Option<String> {
+    let mut command = git_command();
+    command
+        .arg("symbolic-ref")
+        .arg("--quiet")
+        .arg("--short")
+        .arg("HEAD")
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// checks out the pinned branch unless it already is, a branch that only exists on the
+// remote gets a tracking branch. local changes are never carried over to another branch.
+pub async fn switch_to_pinned_branch(path: &PathBuf, branch: &str) -> Result<(), String> {
+    let current_branch = git_current_branch(path).await;
+    if current_branch.as_deref() == Some(branch) {
+        return Ok(());
+    }
+    let on = current_branch.unwrap_or("a detached HEAD".to_string());
+    let mut status = git_command();
+    status.arg("status").arg("--porcelain").current_dir(path);
+    let status = output(status).await.map_err(|e| e.to_string())?;
+    if !status.stdout.trim_ascii().is_empty() {
+        return Err(format!(
+            "on {} instead of the pinned {}, not switched because of local changes",
+            on, branch
+        ));
+    }
+    let mut switch = git_command();
+    switch.arg("switch").arg(branch).current_dir(path);
+    let switched = output(switch).await.map_err(|e| e.to_string())?;
+    if !switched.status.success() {
+        return Err(format!(
+            "on {} instead of the pinned {}: {}",
+            on,
+            branch,
+            String::from_utf8_lossy(switched.stderr.trim_ascii())
+        ));
+    }
+    Ok(())
+}
+
+// moves a clone that follows releases to the newest tag on the default branch of its
+// remote, with a detached HEAD. Some(tag) when it moved.
+pub async fn checkout_latest_release(path: &PathBuf) -> Result<Option<String>, String> {
+    let git = |args: &[&str]| {
+        let mut command = git_command();
+        command.args(args).current_dir(path);
+        async move {
+            let output = output(command).await.map_err(|e| e.to_string())?;
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(output.stderr.trim_ascii()).to_string());
+            }
+            Ok(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+        }
+    };
+    let remote_name = remote_name(path);
+    git(&["fetch", "--tags", "--prune", remote_name]).await?;
+    let remote_head = format!("{}/HEAD", remote_name);
+    let tag = git(&["describe", "--tags", "--abbrev=0", &remote_head])
+        .await
+        .map_err(|_| format!("no release tag on the default branch of {}", remote_name))?;
+    let tag_commit = git(&["rev-parse", &format!("{}^{{commit}}", tag)]).await?;
+    if git_head(path).await.as_ref() == Some(&tag_commit) {
+        return Ok(None);
+    }
+    if !git(&["status", "--porcelain"]).await?.is_empty() {
+        return Err(format!("not moved to {}, local changes", tag));
+    }
+    git(&["checkout", "--quiet", "--detach", &tag]).await?;
+    Ok(Some(tag))
+}
+
+// commits on the local branch that its remote branch doesn't have.
+pub async fn commits_ahead_of_remote(path: &PathBuf, branch: &str) -> Option<u64> {
+    let mut command = git_command();
+    command
+        .arg("rev-list")
+        .arg("--count")
+        .arg(format!("{}/{}..{}", remote_name(path), branch, branch))
+        .current_dir(path);
+    let output = output(command).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(output.stdout.trim_ascii())
+        .parse::<u64>()
+        .ok()
+}
+
+// a pull that git refused because it would overwrite uncommitted changes.
+pub fn is_dirty_worktree_error(stderr: &str) -> bool {
+    const DIRTY_WORKTREE_ERRORS: [&str; 3] = [
+        "would be overwritten by merge",
+        "Please commit your changes or stash them",
+        "You have unstaged changes",
+    ];
+    DIRTY_WORKTREE_ERRORS
+        .iter()
+        .any(|message| stderr.contains(message))
+}
+
+// things worth a look after a successful pull, e.g. a clone that is not on its default branch
+// and so never gets the new commits of the team.
+// shallow clones are only a warning when they were not made shallow on purpose.
+// a pinned branch was checked out on purpose, the branch is no warning then.
+pub async fn pull_warnings(path: &PathBuf, shallow_expected: bool, pinned: bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !shallow_expected && path.join(".git").join("shallow").exists() {
+        warnings.push("shallow clone, the history is incomplete".to_string());
+    }
+    let remote_ref = parse_default_branch_ref(output(default_branch_ref_command(path)).await);
+    if !pinned && let Some(default_branch) = remote_branch(path, &remote_ref) {
+        let mut command = git_command();
+        command
+            .arg("symbolic-ref")
+            .arg("--quiet")
+            .arg("--short")
+            .arg("HEAD")
+            .current_dir(path);
+        match output(command).await {
+            Ok(output) if output.status.success() => {
+                let branch = String::from_utf8_lossy(output.stdout.trim_ascii()).to_string();
+                if branch != default_branch {
+                    warnings.push(format!("on {} instead of {}", branch, default_branch));
+                }
+            }
+            Ok(_) => warnings.push("detached HEAD".to_string()),
+            Err(_) => {}
+        }
+    }
+    warnings
+}
+
+// one line per local branch: HEAD marker, name, upstream tracking state and committer date.
+pub fn list_local_branches(path: &PathBuf) -> Result<String, std::io::Error> {
+    let output = git_command()
+        .arg("for-each-ref")
+        .arg("refs/heads")
+        .arg("--format=%(HEAD)%1f%(refname:short)%1f%(upstream:track)%1f%(committerdate:unix)")
+        .current_dir(path)
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn delete_branch(path: &PathBuf, branch: &str) -> Result<std::process::Output, std::io::Error> {
+    if read_only::is_read_only() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "deleting branches is not possible with --read-only",
+        ));
+    }
+    return git_command()
+        .arg("branch")
+        .arg("-D")
+        .arg(branch)
+        .current_dir(path)
         .output();
 }
 
+// checking out a branch that only exists on origin creates a tracking branch.
+pub fn switch_branch(
+    path: &PathBuf,
+    branch: &str,
+    create_from: Option<&str>,
+) -> Result<std::process::Output, std::io::Error> {
+    let mut command = git_command();
+    command.arg("switch");
+    match create_from {
+        Some(start_point) => command.arg("--create").arg(branch).arg(start_point),
+        None => command.arg(branch),
+    };
+    return command.current_dir(path).output();
+}
+
+// with a branch it is created or reset to the commit, otherwise HEAD is detached.
+pub fn checkout(
+    path: &PathBuf,
+    commit: &str,
+    branch: Option<&String>,
+) -> Result<std::process::Output, std::io::Error> {
+    let mut command = git_command();
+    command.arg("checkout");
+    match branch {
+        Some(branch) => command.arg("-B").arg(branch),
+        None => command.arg("--detach"),
+    };
+    return command.arg(commit).current_dir(path).output();
+}
+
+// one line per commit: date, author and subject separated by \x1f.
+pub fn git_log_since(
+    path: &PathBuf,
+    revision: &str,
+    since: Duration,
+) -> Result<String, std::io::Error> {
+    let output = git_command()
+        .arg("log")
+        .arg(revision)
+        .arg(format!("--since={} seconds ago", since.as_secs()))
+        .arg("--format=%aI%x1f%an%x1f%s")
+        .current_dir(path)
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn is_auth_error(stderr: &str) -> bool {
+    if let Some(category) = rules::classify(stderr) {
+        return category == Category::Auth || category == Category::Credential;
+    }
+    // "Could not read from remote repository" also follows deleted repos and network errors.
+    const AUTH_ERRORS: [&str; 4] = [
+        "Permission denied (publickey",
+        "Authentication failed",
+        "The requested URL returned error: 401",
+        "The requested URL returned error: 403",
+    ];
+    AUTH_ERRORS
+        .iter()
+        .any(|auth_error| stderr.contains(auth_error))
+}
+
 pub fn find_new_repos(
     remote_repos: &Vec<RemoteRepo>,
     local_repos: &Vec<LocalRepo>,
     github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
 ) -> Vec<RemoteRepo> {
     remote_repos
         .iter()
-        .filter(|repo| !is_known_repo(repo, local_repos, github_team_prefix))
+        .filter(|repo| !is_known_repo(repo, local_repos, github_team_prefix, directories))
         .cloned()
         .collect()
 }
@@ -58,25 +1212,111 @@ pub fn find_archived_local_repos(
     remote_archived_repos: &Vec<RemoteRepo>,
     local_repos: &Vec<LocalRepo>,
     github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
 ) -> Vec<RemoteRepo> {
     remote_archived_repos
         .iter()
-        .filter(|repo| is_known_repo(repo, local_repos, github_team_prefix))
+        .filter(|repo| is_known_repo(repo, local_repos, github_team_prefix, directories))
         .cloned()
         .collect()
 }
 
+// the repo name without prefix, only used for repos without a recorded directory.
+pub fn conventional_dir_name(remote_repo: &RemoteRepo, github_team_prefix: &String) -> String {
+    match &remote_repo.dir_name {
+        Some(dir_name) => dir_name.clone(),
+        None => remote_repo.name.replace(github_team_prefix.as_str(), ""),
+    }
+}
+
+// the recorded directory wins, so a changed naming rule doesn't lose existing clones.
+pub fn local_dir_name(
+    remote_repo: &RemoteRepo,
+    github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
+) -> String {
+    match directories.get(&remote_repo.name) {
+        Some(dir_name) => dir_name.clone(),
+        None => conventional_dir_name(remote_repo, github_team_prefix),
+    }
+}
+
 pub fn is_known_repo(
     remote_repo: &RemoteRepo,
     local_repos: &Vec<LocalRepo>,
     github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
 ) -> bool {
-    for local_repo in local_repos {
-        if local_repo.name == remote_repo.name.replace(github_team_prefix.as_str(), "") {
-            return true;
-        }
+    find_local_repo(remote_repo, local_repos, github_team_prefix, directories).is_some()
+}
+
+pub fn find_local_repo<'a>(
+    remote_repo: &RemoteRepo,
+    local_repos: &'a Vec<LocalRepo>,
+    github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
+) -> Option<&'a LocalRepo> {
+    let dir_name = local_dir_name(remote_repo, github_team_prefix, directories);
+    local_repos
+        .iter()
+        .find(|local_repo| local_repo.name == dir_name)
+}
+
+// ssh and https urls of the same repo normalize to the same host/owner/repo.
+pub fn normalize_url(url: &str) -> String {
+    let url = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_lowercase();
+    if let Some(path) = url.strip_prefix("https://") {
+        return path.to_string();
+    }
+    if let Some(path) = url.strip_prefix("ssh://git@") {
+        return path.to_string();
     }
-    return false;
+    match url.strip_prefix("git@") {
+        Some(path) => path.replacen(':', "/", 1),
+        None => url,
+    }
+}
+
+pub fn is_same_remote(url: &str, remote_repo: &RemoteRepo) -> bool {
+    let url = normalize_url(url);
+    url == normalize_url(&remote_repo.ssh_url) || url == normalize_url(&remote_repo.clone_url)
+}
+
+pub fn set_remote_url(path: &PathBuf, url: &str) -> Result<std::process::Output, std::io::Error> {
+    return git_command()
+        .arg("remote")
+        .arg("set-url")
+        .arg(remote_name(path))
+        .arg(url)
+        .current_dir(path)
+        .output();
+}
+
+pub fn set_config(
+    path: &PathBuf,
+    key: &str,
+    value: &str,
+) -> Result<std::process::Output, std::io::Error> {
+    return git_command()
+        .arg("config")
+        .arg(key)
+        .arg(value)
+        .current_dir(path)
+        .output();
+}
+
+pub fn set_push_url(path: &PathBuf, url: &str) -> Result<std::process::Output, std::io::Error> {
+    return git_command()
+        .arg("remote")
+        .arg("set-url")
+        .arg("--push")
+        .arg(remote_name(path))
+        .arg(url)
+        .current_dir(path)
+        .output();
 }
 
 pub fn is_git_repo(path: &String) -> bool {
@@ -99,14 +1339,32 @@ pub fn is_git_repo(path: &String) -> bool {
     false
 }
 
+// only direct children of the repo root dir, there is no recursive discovery. repos nested
+// in the working tree of a clone, e.g. vendored checkouts or test fixtures, are never found.
 pub fn list_local_repos(path: &PathBuf) -> Vec<LocalRepo> {
     let mut repos: Vec<LocalRepo> = Vec::new();
     if let Ok(entries) = fs::read_dir(path) {
         for entry in entries {
             if let Ok(subdir) = entry {
+                // junctions and symlinks may point outside the root dir or back into it.
+                if cfg!(windows)
+                    && subdir
+                        .file_type()
+                        .is_ok_and(|file_type| file_type.is_symlink())
+                {
+                    continue;
+                }
+                // clones in progress or interrupted ones are not repos yet.
+                if subdir
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(CLONE_TMP_SUFFIX)
+                {
+                    continue;
+                }
                 if is_git_repo(&subdir.path().to_string_lossy().to_string()) {
                     repos.push(LocalRepo {
-                        name: subdir.file_name().into_string().unwrap(),
+                        name: subdir.file_name().to_string_lossy().to_string(),
                         path: subdir.path(),
                     });
                 }
@@ -116,71 +1374,6 @@ pub fn list_local_repos(path: &PathBuf) -> Vec<LocalRepo> {
     repos
 }
 
-pub async fn get_repos(
-    client: &Client,
-    token: &String,
-    page: i32,
-    github_team_prefix: &String,
-    github_team_repo_url: &Url,
-) -> Option<Vec<RemoteRepo>> {
-    let response = match client
-        .get(github_team_repo_url.clone())
-        .header(ACCEPT, "application/vnd.github.v3+json")
-        .header(USER_AGENT, "reposync")
-        .bearer_auth(token)
-        .query(&[("per_page", "100"), ("page", page.to_string().as_str())])
-        .send()
-        .await
-    {
-        Ok(resp) => resp,
-        Err(e) => {
-            println!("Request failed: {}", e);
-            return None; // Return empty vector
-        }
-    };
-
-    match response.json::<Vec<RemoteRepo>>().await {
-        Ok(repos) => {
-            let next_paged_repos = repos
-                .into_iter()
-                .filter(|repo| repo.name.starts_with(github_team_prefix.as_str()))
-                .collect::<Vec<RemoteRepo>>();
-            if next_paged_repos.is_empty() {
-                return None;
-            } else {
-                return Some(next_paged_repos);
-            }
-        }
-
-        Err(e) => {
-            println!("Failed to parse JSON: {}", e);
-            return None;
-        }
-    }
-}
-
-pub async fn get_all_repos(
-    token: &String,
-    github_team_prefix: &String,
-    github_team_repo_url: &Url,
-) -> Vec<RemoteRepo> {
-    let client = Client::new();
-
-    let mut repos: Vec<RemoteRepo> = Vec::new();
-    let mut page = 1;
-    while let Some(page_repos) = get_repos(
-        &client,
-        &token,
-        page,
-        github_team_prefix,
-        github_team_repo_url,
-    )
-    .await {
-        repos.extend(page_repos);
-        page += 1;
-    }
-    return repos;
-}
 pub async fn list_active_github_team_repos(git_repos: Vec<RemoteRepo>) -> Vec<RemoteRepo> {
     return git_repos
         .into_iter()
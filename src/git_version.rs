@@ -0,0 +1,94 @@
+use std::sync::OnceLock;
+
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+
+use crate::git;
+
+// major, minor and patch, e.g. (2, 45, 1).
+pub type Version = (u32, u32, u32);
+
+// flags by argument id that make reposync run git features older gits don't have.
+const REQUIRED_BY_FLAGS: [(&str, &str, Version); 4] = [
+    ("prune_tags", "--prune-tags", (2, 17, 0)),
+    ("shallow_since", "--shallow-since", (2, 11, 0)),
+    ("reference_dir", "--reference-dir", (2, 11, 0)),
+    ("allowed_signers", "--allowed-signers", (2, 34, 0)),
+];
+
+// clone args a classify script may return.
+const REQUIRED_BY_CLONE_ARGS: [(&str, Version); 3] = [
+    ("--filter", (2, 19, 0)),
+    ("--sparse", (2, 25, 0)),
+    ("--also-filter-submodules", (2, 36, 0)),
+];
+
+// e.g. git version 2.39.3 (Apple Git-145) or git version 2.45.1.windows.1.
+fn parse(version: &str) -> Option<Version> {
+    let mut numbers = version
+        .trim()
+        .strip_prefix("git version ")?
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|number| number.parse::<u32>().ok());
+    let major = numbers.next()??;
+    let minor = numbers.next()??;
+    let patch = numbers.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+// probed once, None when git is missing or prints something unexpected.
+pub fn detected() -> Option<Version> {
+    static DETECTED: OnceLock<Option<Version>> = OnceLock::new();
+    *DETECTED.get_or_init(|| git::git_version().as_deref().and_then(parse))
+}
+
+fn format(version: Version) -> String {
+    format!("{}.{}.{}", version.0, version.1, version.2)
+}
+
+// an unknown version is assumed to be recent, git reports its own errors then.
+pub fn supports(required: Version) -> bool {
+    detected().is_none_or(|version| version >= required)
+}
+
+fn requirement_error(what: &str, required: Version) -> Option<String> {
+    if supports(required) {
+        return None;
+    }
+    Some(format!(
+        "{} needs git {} or newer, found {}",
+        what,
+        format(required),
+        detected().map(format).unwrap_or_default()
+    ))
+}
+
+// refuses flags the installed git can't run before anything starts, instead of failing
+// every repo with the usage output of git.
+pub fn check_flags(cli: &ArgMatches) -> Result<(), String> {
+    let errors: Vec<String> = REQUIRED_BY_FLAGS
+        .iter()
+        .filter(|(id, _, _)| {
+            cli.value_source(id)
+                .is_some_and(|source| source != ValueSource::DefaultValue)
+        })
+        .filter_map(|(_, flag, required)| requirement_error(flag, *required))
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors.join(", "))
+}
+
+pub fn check_clone_args(clone_args: &[String]) -> Result<(), String> {
+    for (arg, required) in REQUIRED_BY_CLONE_ARGS {
+        if clone_args
+            .iter()
+            .any(|clone_arg| clone_arg == arg || clone_arg.starts_with(&format!("{}=", arg)))
+            && let Some(e) = requirement_error(arg, required)
+        {
+            return Err(e);
+        }
+    }
+    Ok(())
+}
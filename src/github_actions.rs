@@ -0,0 +1,51 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::failure_log;
+use crate::failure_log::FailedRepo;
+
+// % and line breaks would end the message of a workflow command early.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+// e.g. ::error title=orders-api::fatal: repository not found, shown on the run in the Actions UI.
+pub fn annotate(level: &str, title: &str, message: &str) {
+    println!(
+        "::{} title={}::{}",
+        level,
+        escape_property(title),
+        escape_data(message)
+    );
+}
+
+// appends to the summary of the job step, the counts as a table and the failed repos with
+// their errors.
+pub fn write_job_summary(
+    counts: &[(&str, usize)],
+    failed_repos: &[FailedRepo],
+) -> Result<(), String> {
+    let path = std::env::var("GITHUB_STEP_SUMMARY")
+        .map_err(|_| "GITHUB_STEP_SUMMARY is not set, not running in GitHub Actions".to_string())?;
+    let mut summary = String::from("## reposync\n\n| repos | count |\n| --- | --- |\n");
+    for (label, count) in counts.iter().filter(|(_, count)| *count > 0) {
+        summary.push_str(&format!("| {} | {} |\n", label, count));
+    }
+    if !failed_repos.is_empty() {
+        summary.push('\n');
+        summary.push_str(&failure_log::markdown_summary(failed_repos));
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    writeln!(file, "{}", summary).map_err(|e| format!("{}: {}", path, e))
+}
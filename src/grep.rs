@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::list_local_repos;
+use crate::queue::WorkQueue;
+use crate::style;
+
+// runs git grep in all repos at once and prints the matches prefixed with the repo,
+// sorted by repo so the output is the same on every run.
+pub async fn run(repo_root_dir: &PathBuf, grep_args: &ArgMatches, jobs: usize) -> bool {
+    let pattern = grep_args.get_one::<String>("pattern").unwrap();
+    let ignore_case = grep_args.get_flag("ignore_case");
+    let fixed_strings = grep_args.get_flag("fixed_strings");
+    let mut work_queue = WorkQueue::new(jobs);
+    for local_repo in list_local_repos(repo_root_dir) {
+        let pattern = pattern.clone();
+        work_queue.push(async move {
+            let result =
+                git::git_grep(&local_repo.path, &pattern, ignore_case, fixed_strings).await;
+            (local_repo.name, result)
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(result) = work_queue.next().await {
+        results.push(result);
+    }
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut found = false;
+    for (name, result) in results {
+        match result {
+            Ok(output) if output.status.success() => {
+                found = true;
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    println!("{}/{}", style::paint(&name, style::WARNING), line);
+                }
+            }
+            Ok(output) if output.status.code() == Some(1) => {}
+            Ok(output) => println!(
+                "{}: {}",
+                style::paint(&name, style::ERROR),
+                String::from_utf8_lossy(output.stderr.trim_ascii())
+            ),
+            Err(e) => println!("{}: {}", style::paint(&name, style::ERROR), e),
+        }
+    }
+    found
+}
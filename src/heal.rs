@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::git;
+use crate::git::LocalRepo;
+
+pub const QUARANTINE_DIR: &str = ".reposync-quarantine";
+
+// moves a corrupt clone into the quarantine dir next to it, clones it again and copies dirty files back.
+pub fn heal(local_repo: &LocalRepo) -> Result<String, String> {
+    let url = git::remote_url(&local_repo.path)
+        .ok_or("cannot heal, the remote url is unreadable".to_string())?;
+    let dirty_files = git::dirty_files(&local_repo.path);
+    let repo_root_dir = local_repo
+        .path
+        .parent()
+        .ok_or("cannot heal a repo without parent dir".to_string())?;
+    let quarantine_path = repo_root_dir.join(QUARANTINE_DIR).join(format!(
+        "{}-{}",
+        local_repo.name,
+        chrono::Local::now().format("%Y-%m-%dT%H-%M-%S")
+    ));
+    fs::create_dir_all(repo_root_dir.join(QUARANTINE_DIR)).map_err(|e| e.to_string())?;
+    fs::rename(&local_repo.path, &quarantine_path).map_err(|e| e.to_string())?;
+
+    let output = git::git_clone_into(&url, &local_repo.path).map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "re-clone failed, the old clone is in {}:\n{}",
+            quarantine_path.display(),
+            String::from_utf8_lossy(output.stderr.trim_ascii())
+        ));
+    }
+
+    let Some(dirty_files) = dirty_files else {
+        return Ok(format!(
+            "re-cloned, dirty files could not be determined, the old clone is in {}",
+            quarantine_path.display()
+        ));
+    };
+    let mut failed_files: Vec<String> = Vec::new();
+    for dirty_file in &dirty_files {
+        if let Err(e) = restore_file(&quarantine_path, &local_repo.path, dirty_file) {
+            failed_files.push(format!("{}: {}", dirty_file, e));
+        }
+    }
+    let mut message = format!(
+        "re-cloned and restored {} dirty files, the old clone is in {}",
+        dirty_files.len() - failed_files.len(),
+        quarantine_path.display()
+    );
+    for failed_file in failed_files {
+        message.push_str(&format!("\nnot restored: {}", failed_file));
+    }
+    Ok(message)
+}
+
+fn restore_file(from_repo: &PathBuf, to_repo: &PathBuf, file: &str) -> Result<(), std::io::Error> {
+    let target = to_repo.join(file);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(from_repo.join(file), target)?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+// runs per repo that are kept in the state.
+pub const HISTORY_LENGTH: usize = 10;
+// more failures than this within the history make a repo flaky, if it also succeeded.
+pub const FLAKY_THRESHOLD: usize = 3;
+// weight of a run relative to the next newer one in the health score.
+const DECAY: f64 = 0.7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Outcome {
+    pub time: String,
+    pub failed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // how long the pull or clone took, runs recorded before durations were kept have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+// keeps the first line of an error, that's what identifies it.
+pub fn error_excerpt(message: &str) -> String {
+    let line = message
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+    line.chars().take(200).collect()
+}
+
+pub fn record(
+    history: &mut BTreeMap<String, Vec<Outcome>>,
+    repo: &str,
+    error: Option<&str>,
+    duration: Duration,
+) {
+    let outcomes = history.entry(repo.to_string()).or_default();
+    outcomes.push(Outcome {
+        time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        failed: error.is_some(),
+        error: error.map(error_excerpt),
+        duration_ms: Some(duration.as_millis() as u64),
+    });
+    if outcomes.len() > HISTORY_LENGTH {
+        outcomes.drain(..outcomes.len() - HISTORY_LENGTH);
+    }
+}
+
+// share of successful runs where each run weighs DECAY times as much as the next newer one,
+// so a repo recovers its score quickly once it works again. 1.0 is perfectly healthy.
+pub fn health(outcomes: &Vec<Outcome>) -> f64 {
+    let mut weight = 1.0;
+    let mut total = 0.0;
+    let mut healthy = 0.0;
+    for outcome in outcomes.iter().rev() {
+        total += weight;
+        if !outcome.failed {
+            healthy += weight;
+        }
+        weight *= DECAY;
+    }
+    if total == 0.0 { 1.0 } else { healthy / total }
+}
+
+// repos that fail intermittently, as opposed to repos that are simply broken, least healthy first.
+pub fn flaky(history: &BTreeMap<String, Vec<Outcome>>) -> Vec<(&String, &Vec<Outcome>)> {
+    let mut flaky: Vec<(&String, &Vec<Outcome>)> = history
+        .iter()
+        .filter(|(_, outcomes)| {
+            let failures = outcomes.iter().filter(|outcome| outcome.failed).count();
+            failures > FLAKY_THRESHOLD && failures < outcomes.len()
+        })
+        .collect();
+    flaky.sort_by(|(_, a), (_, b)| health(a).total_cmp(&health(b)));
+    flaky
+}
+
+// mean duration of the recorded runs, None for repos without any.
+pub fn expected_duration(outcomes: &[Outcome]) -> Option<Duration> {
+    let durations: Vec<u64> = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.duration_ms)
+        .collect();
+    if durations.is_empty() {
+        return None;
+    }
+    Some(Duration::from_millis(
+        durations.iter().sum::<u64>() / durations.len() as u64,
+    ))
+}
@@ -0,0 +1,49 @@
+use clap::ArgMatches;
+
+use crate::state;
+use crate::style;
+
+fn repos(args: &ArgMatches) -> Vec<String> {
+    args.get_many::<String>("repos")
+        .unwrap_or_default()
+        .cloned()
+        .collect()
+}
+
+pub fn run(ignore_args: &ArgMatches) {
+    let mut sync_state = state::load();
+    match ignore_args.subcommand() {
+        Some(("add", add_args)) => {
+            for repo in repos(add_args) {
+                if sync_state.ignored.insert(repo.clone()) {
+                    println!("{}: ignored", repo);
+                }
+            }
+        }
+        Some(("remove", remove_args)) => {
+            for repo in repos(remove_args) {
+                if sync_state.ignored.remove(&repo) {
+                    println!("{}: no longer ignored", repo);
+                } else {
+                    println!("{}: not ignored", repo);
+                }
+            }
+        }
+        _ => {
+            if sync_state.ignored.is_empty() {
+                println!("no ignored repos");
+            }
+            for repo in &sync_state.ignored {
+                println!("{}", repo);
+            }
+            return;
+        }
+    }
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::PathBuf;
+
+use reqwest::Url;
+
+use crate::api;
+use crate::config;
+use crate::config::Defaults;
+use crate::git::RemoteRepo;
+use crate::prompt;
+use crate::provider;
+use crate::provider::Provider;
+use crate::style;
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// the first page is enough to tell a wrong team, a wrong prefix or a token without access.
+async fn list_github_repos(token: &str, url: &Url) -> Result<Vec<RemoteRepo>, String> {
+    let api_client = api::ApiClient::new(token.to_string(), 10, 1, &api::HttpOptions::default())?;
+    let response = api_client.get(url.clone(), &[("per_page", "100")]).await?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|error| error["message"].as_str().map(|message| message.to_string()))
+            .unwrap_or(text);
+        return Err(format!("{}: {}", status, message));
+    }
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+async fn validate(defaults: &Defaults, token: Result<String, String>) -> Result<String, String> {
+    let provider_name = defaults.provider.as_deref().unwrap_or("github");
+    let prefix = defaults.github_team_prefix.clone().unwrap_or_default();
+    let repos = if provider_name == "github" {
+        let url = defaults
+            .github_team_repo_url
+            .as_deref()
+            .unwrap_or("")
+            .parse::<Url>()
+            .map_err(|e| e.to_string())?;
+        list_github_repos(&token?, &url).await?
+    } else {
+        provider::External {
+            name: provider_name.to_string(),
+            github_team_prefix: &prefix,
+        }
+        .list_repos()
+        .await?
+    };
+    let matching = repos
+        .iter()
+        .filter(|repo| repo.name.starts_with(prefix.as_str()))
+        .count();
+    if repos.is_empty() {
+        return Err("the team has no repos".to_string());
+    }
+    if matching == 0 {
+        return Err(format!(
+            "none of the {} repos starts with the prefix {}",
+            repos.len(),
+            prefix
+        ));
+    }
+    Ok(format!(
+        "found {} team repos, {} of them with the prefix",
+        repos.len(),
+        matching
+    ))
+}
+
+// keeps the rest of an existing config file, only the defaults table is replaced.
+fn write(path: &PathBuf, defaults: &Defaults) -> Result<(), String> {
+    let mut table = match fs::read_to_string(path) {
+        Ok(content) => content
+            .parse::<toml::Table>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    table.insert(
+        "defaults".to_string(),
+        toml::Value::try_from(defaults).map_err(|e| e.to_string())?,
+    );
+    let content = toml::to_string(&table).map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// asks for the values of the required flags, checks them against the API and writes them
+// as defaults to the config file, so a plain reposync works afterwards.
+async fn write_defaults(config_path: &PathBuf) -> Result<(), String> {
+    let existing = config::load(Some(config_path))?;
+    if existing.defaults.github_team_repo_url.is_some()
+        && !prompt::confirm(&format!(
+            "{} already has defaults, replace them?",
+            config_path.display()
+        ))
+    {
+        return Err("nothing written".to_string());
+    }
+    let provider_name = prompt::ask(
+        "provider, github or the name of a reposync-provider-<name> plugin:",
+        "github",
+    );
+    let api_base = prompt::ask("GitHub API:", "https://api.github.com");
+    let organization = prompt::ask("organization:", "");
+    let team = prompt::ask("team slug:", "");
+    let token_sources = [
+        "the GITHUB_TOKEN environment variable",
+        "a command that prints it, e.g. gh auth token",
+    ];
+    let token_command =
+        match prompt::choose("where does the GitHub token come from?", &token_sources) {
+            Some(1) => Some(prompt::ask("command:", "gh auth token")),
+            _ => None,
+        };
+    let repo_root_dir = expand_home(&prompt::ask("repo root dir:", "~/repos"));
+    let prefix = prompt::ask(
+        "prefix of the team repos, removed from the directory names, empty for none:",
+        "",
+    );
+    let defaults = Defaults {
+        provider: Some(provider_name),
+        github_team_repo_url: Some(format!(
+            "{}/orgs/{}/teams/{}/repos",
+            api_base.trim_end_matches('/'),
+            organization,
+            team
+        )),
+        repo_root_dir: Some(repo_root_dir),
+        github_team_prefix: Some(prefix),
+        token_command,
+    };
+
+    let token = match &defaults.token_command {
+        Some(token_command) => config::token_from_command(token_command),
+        None => std::env::var("GITHUB_TOKEN").map_err(|_| "GITHUB_TOKEN is not set".to_string()),
+    };
+    match validate(&defaults, token).await {
+        Ok(message) => println!("{}", style::paint(&message, style::OK)),
+        Err(e) => {
+            println!("{}: {}", style::paint("check failed", style::ERROR), e);
+            if !prompt::confirm("write the config anyway?") {
+                return Err("nothing written".to_string());
+            }
+        }
+    }
+    write(config_path, &defaults)?;
+    println!("wrote {}", config_path.display());
+    Ok(())
+}
+
+pub async fn run(config_path: Option<&PathBuf>) {
+    let Some(config_path) = config_path.cloned().or_else(config::config_file) else {
+        println!(
+            "{}: no config directory, neither HOME nor XDG_CONFIG_HOME is set",
+            style::paint("init failed", style::ERROR)
+        );
+        std::process::exit(1);
+    };
+    if let Err(e) = write_defaults(&config_path).await {
+        println!("{}: {}", style::paint("init failed", style::ERROR), e);
+        std::process::exit(1);
+    }
+}
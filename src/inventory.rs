@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git::LocalRepo;
+use crate::git::list_local_repos;
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub repo: String,
+    pub manifest: &'static str,
+    pub name: String,
+    pub version: String,
+}
+
+// the value of `key = "value"`, None for anything else.
+fn quoted_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (line_key, value) = line.split_once('=')?;
+    if line_key.trim() != key {
+        return None;
+    }
+    Some(value.trim().trim_matches('"'))
+}
+
+fn is_cargo_dependency_table(table: &str) -> bool {
+    table == "dependencies"
+        || table == "dev-dependencies"
+        || table == "build-dependencies"
+        || table == "workspace.dependencies"
+        || (table.starts_with("target.") && table.ends_with("dependencies"))
+}
+
+// covers `name = "1.0"`, `name = { version = "1.0" }` and `[dependencies.name]` tables,
+// which is what Cargo.toml files look like in practice, without a full TOML parser.
+fn parse_cargo_toml(content: &str) -> Vec<(String, String)> {
+    let mut dependencies = Vec::new();
+    let mut in_dependencies = false;
+    let mut dependency_table: Option<String> = None;
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or("").trim();
+        if let Some(table) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let table = table.trim();
+            in_dependencies = is_cargo_dependency_table(table);
+            dependency_table = table
+                .rsplit_once('.')
+                .filter(|(parent, _)| is_cargo_dependency_table(parent))
+                .map(|(_, name)| name.to_string());
+            continue;
+        }
+        if let Some(name) = &dependency_table {
+            if let Some(version) = quoted_value(line, "version") {
+                dependencies.push((name.clone(), version.to_string()));
+            }
+            continue;
+        }
+        if !in_dependencies {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let version = if value.starts_with('"') {
+            value.trim_matches('"').to_string()
+        } else {
+            value
+                .trim_matches(['{', '}'])
+                .split(',')
+                .find_map(|field| quoted_value(field, "version"))
+                .unwrap_or("*")
+                .to_string()
+        };
+        dependencies.push((name.trim().to_string(), version));
+    }
+    dependencies
+}
+
+fn parse_package_json(content: &str) -> Vec<(String, String)> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let mut dependencies = Vec::new();
+    for section in ["dependencies", "devDependencies", "peerDependencies"] {
+        if let Some(section) = package[section].as_object() {
+            for (name, version) in section {
+                dependencies.push((name.clone(), version.as_str().unwrap_or("*").to_string()));
+            }
+        }
+    }
+    dependencies
+}
+
+fn parse_go_mod(content: &str) -> Vec<(String, String)> {
+    let mut dependencies = Vec::new();
+    let mut in_require_block = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line == "require (" {
+            in_require_block = true;
+            continue;
+        }
+        if in_require_block && line == ")" {
+            in_require_block = false;
+            continue;
+        }
+        let requirement = if in_require_block {
+            line
+        } else if let Some(requirement) = line.strip_prefix("require ") {
+            requirement
+        } else {
+            continue;
+        };
+        if let [name, version] = requirement.split_whitespace().collect::<Vec<&str>>()[..] {
+            dependencies.push((name.to_string(), version.to_string()));
+        }
+    }
+    dependencies
+}
+
+// name and version of each dependency in a manifest.
+type ManifestParser = fn(&str) -> Vec<(String, String)>;
+
+// manifests in the root of each repo, nested projects are not searched.
+pub fn collect_dependencies(local_repos: &Vec<LocalRepo>) -> Vec<Dependency> {
+    let parsers: [(&'static str, ManifestParser); 3] = [
+        ("Cargo.toml", parse_cargo_toml),
+        ("package.json", parse_package_json),
+        ("go.mod", parse_go_mod),
+    ];
+    let mut dependencies = Vec::new();
+    for local_repo in local_repos {
+        for (manifest, parse) in parsers {
+            let path: PathBuf = local_repo.path.join(manifest);
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            for (name, version) in parse(&content) {
+                dependencies.push(Dependency {
+                    repo: local_repo.name.clone(),
+                    manifest,
+                    name,
+                    version,
+                });
+            }
+        }
+    }
+    dependencies
+}
+
+// one block per dependency with the repos using each version, e.g. to find the last users of v1.
+pub fn render(dependencies: &Vec<Dependency>, name: Option<&String>) -> String {
+    let mut versions: BTreeMap<(&str, &str), BTreeMap<&str, BTreeSet<&str>>> = BTreeMap::new();
+    for dependency in dependencies {
+        if name.is_some_and(|name| *name != dependency.name) {
+            continue;
+        }
+        versions
+            .entry((dependency.name.as_str(), dependency.manifest))
+            .or_default()
+            .entry(dependency.version.as_str())
+            .or_default()
+            .insert(dependency.repo.as_str());
+    }
+    if versions.is_empty() {
+        return "no dependencies found\n".to_string();
+    }
+    let mut report = String::new();
+    for ((name, manifest), versions) in versions {
+        report.push_str(&format!("{} ({})\n", name, manifest));
+        for (version, repos) in versions {
+            let repos: Vec<&str> = repos.into_iter().collect();
+            report.push_str(&format!("  {}: {}\n", version, repos.join(", ")));
+        }
+    }
+    report
+}
+
+pub fn run(repo_root_dir: &PathBuf, inventory_args: &ArgMatches) {
+    let dependencies = collect_dependencies(&list_local_repos(repo_root_dir));
+    print!(
+        "{}",
+        render(
+            &dependencies,
+            inventory_args.get_one::<String>("dependency")
+        )
+    );
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::failure_log;
+use crate::failure_log::FailedRepo;
+use crate::paths;
+use crate::style;
+
+// the outcome of the last sync in a few numbers, for shell prompts and status bars that
+// can't afford to run a sync or parse its output.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LastRun {
+    // RFC 3339, e.g. 2024-05-02T08:15:00+02:00.
+    pub finished_at: String,
+    pub duration_secs: u64,
+    pub up_to_date: usize,
+    pub updated: usize,
+    pub cloned: usize,
+    pub skipped: usize,
+    // repos the sync didn't pull without failing, because of local changes git would
+    // overwrite or --deadline.
+    pub behind: usize,
+    // repos with local commits on the default branch that are on no remote branch.
+    #[serde(default)]
+    pub ahead: usize,
+    // repos that didn't fail but need a look, e.g. a shallow clone or a diverged pin.
+    pub warnings: usize,
+    pub failed: usize,
+    #[serde(default)]
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Failure {
+    pub name: String,
+    pub operation: String,
+    // the fatal: or error: line of git.
+    pub error: String,
+}
+
+impl LastRun {
+    pub fn failures(failed_repos: &[FailedRepo]) -> Vec<Failure> {
+        failed_repos
+            .iter()
+            .map(|failed_repo| Failure {
+                name: failed_repo.name.to_string(),
+                operation: failed_repo.operation.to_string(),
+                error: failure_log::excerpt(failed_repo.output),
+            })
+            .collect()
+    }
+}
+
+// e.g. ⇣3 ⇡1 ✖1, or v3 ^1 x1 with --ascii. empty when the last sync left nothing to look at
+// so the prompt stays quiet.
+pub fn prompt_segment(last_run: &LastRun) -> String {
+    let (behind, ahead, failed) = if style::is_ascii() {
+        ("v", "^", "x")
+    } else {
+        ("⇣", "⇡", "✖")
+    };
+    let mut parts = Vec::new();
+    if last_run.behind > 0 {
+        parts.push(format!("{}{}", behind, last_run.behind));
+    }
+    if last_run.ahead > 0 {
+        parts.push(format!("{}{}", ahead, last_run.ahead));
+    }
+    if last_run.failed > 0 {
+        parts.push(format!("{}{}", failed, last_run.failed));
+    }
+    parts.join(" ")
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("last_run.json"))
+}
+
+// replaced in one rename, so a prompt never reads a half written file.
+pub fn write(path: &PathBuf, last_run: &LastRun) -> Result<(), String> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(last_run).map_err(|e| e.to_string())?;
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, json + "\n").map_err(|e| format!("{}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn load(path: &PathBuf) -> Result<LastRun, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// prints the segment for the shell prompt, nothing before the first sync.
+pub fn run(status_file: Option<&PathBuf>) {
+    let Some(status_file) = status_file.cloned().or_else(default_path) else {
+        return;
+    };
+    if !status_file.exists() {
+        return;
+    }
+    match load(&status_file) {
+        Ok(last_run) => {
+            let segment = prompt_segment(&last_run);
+            if !segment.is_empty() {
+                println!("{}", segment);
+            }
+        }
+        Err(e) => {
+            println!(
+                "{}: {}",
+                style::paint("failed to read the status file", style::ERROR),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use clap::ArgMatches;
+
+use crate::git::RemoteRepo;
+use crate::provider;
+use crate::provider::Provider;
+use crate::style;
+
+fn license_name(remote_repo: &RemoteRepo) -> Option<String> {
+    let license = remote_repo.license.as_ref()?;
+    match (&license.spdx_id, &license.name) {
+        (Some(spdx_id), _) if spdx_id != "NOASSERTION" => Some(spdx_id.clone()),
+        (_, Some(name)) => Some(name.clone()),
+        _ => Some("NOASSERTION".to_string()),
+    }
+}
+
+// active team repos per license, returns the repos without one.
+pub fn report(remote_repos: &Vec<RemoteRepo>) -> Vec<String> {
+    let mut licensed: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    let mut missing: Vec<String> = Vec::new();
+    for remote_repo in remote_repos
+        .iter()
+        .filter(|remote_repo| !remote_repo.archived)
+    {
+        match license_name(remote_repo) {
+            Some(license) => licensed.entry(license).or_default().push(&remote_repo.name),
+            None => missing.push(remote_repo.name.clone()),
+        }
+    }
+    for (license, mut repos) in licensed {
+        repos.sort();
+        println!("{}: {}", style::paint(&license, style::OK), repos.len());
+        for repo in repos {
+            println!("  {}", repo);
+        }
+    }
+    missing.sort();
+    if !missing.is_empty() {
+        println!(
+            "{}: {}",
+            style::paint("Without license", style::WARNING),
+            missing.len()
+        );
+        for repo in &missing {
+            println!("  {}", repo);
+        }
+    }
+    missing
+}
+
+pub async fn run(provider: &dyn Provider, licenses_args: &ArgMatches) {
+    let remote_repos = provider::list_remote_repos(provider).await;
+    let missing = report(&remote_repos);
+    if licenses_args.get_flag("fail_on_missing_license") && !missing.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::time_format;
+
+fn print_table(header: &[&str], rows: &Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = header.iter().map(|column| column.len()).collect();
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
+    }
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| format!("{:width$}", cell, width = widths[index]))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!(
+        "{}",
+        format_row(header.iter().map(|column| column.to_string()).collect())
+    );
+    for row in rows {
+        println!("{}", format_row(row.clone()));
+    }
+}
+
+fn language_column(remote_repo: &RemoteRepo) -> String {
+    remote_repo.language.clone().unwrap_or("-".to_string())
+}
+
+fn topics_column(remote_repo: &RemoteRepo) -> String {
+    if remote_repo.topics.is_empty() {
+        return "-".to_string();
+    }
+    remote_repo.topics.join(",")
+}
+
+fn pushed_column(remote_repo: &RemoteRepo) -> String {
+    remote_repo
+        .pushed_at
+        .as_deref()
+        .map(time_format::timestamp_str)
+        .unwrap_or("-".to_string())
+}
+
+// FETCH_HEAD is written on every fetch and pull, so its age is the time since the last sync.
+fn fetched_column(local_repo: &LocalRepo) -> String {
+    fs::metadata(local_repo.path.join(".git").join("FETCH_HEAD"))
+        .and_then(|metadata| metadata.modified())
+        .map(time_format::system_time)
+        .unwrap_or("-".to_string())
+}
+
+fn matches_language(remote_repo: &RemoteRepo, language: Option<&String>) -> bool {
+    language.is_none_or(|language| remote_repo.has_language(language))
+}
+
+// all team repos and where they are cloned.
+pub fn list(
+    local_repos: &Vec<LocalRepo>,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
+    language: Option<&String>,
+) {
+    let rows: Vec<Vec<String>> = remote_repos
+        .iter()
+        .filter(|remote_repo| matches_language(remote_repo, language))
+        .map(|remote_repo| {
+            let local_dir =
+                git::find_local_repo(remote_repo, local_repos, github_team_prefix, directories)
+                    .map(|local_repo| local_repo.name.clone())
+                    .unwrap_or("-".to_string());
+            vec![
+                remote_repo.name.clone(),
+                local_dir,
+                if remote_repo.archived {
+                    "archived".to_string()
+                } else {
+                    "active".to_string()
+                },
+                pushed_column(remote_repo),
+                language_column(remote_repo),
+                topics_column(remote_repo),
+            ]
+        })
+        .collect();
+    print_table(
+        &["REPO", "LOCAL DIR", "STATE", "PUSHED", "LANGUAGE", "TOPICS"],
+        &rows,
+    );
+}
+
+// working tree state of the local clones of team repos.
+pub fn status(
+    local_repos: &Vec<LocalRepo>,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    directories: &BTreeMap<String, String>,
+    language: Option<&String>,
+) {
+    let rows: Vec<Vec<String>> = remote_repos
+        .iter()
+        .filter(|remote_repo| matches_language(remote_repo, language))
+        .filter_map(|remote_repo| {
+            let local_repo =
+                git::find_local_repo(remote_repo, local_repos, github_team_prefix, directories)?;
+            let dirty = git::dirty_files(&local_repo.path)
+                .map(|files| files.len().to_string())
+                .unwrap_or("?".to_string());
+            let unpushed = git::unpushed_commit_count(&local_repo.path)
+                .map(|count| count.to_string())
+                .unwrap_or("?".to_string());
+            Some(vec![
+                local_repo.name.clone(),
+                dirty,
+                unpushed,
+                fetched_column(local_repo),
+                language_column(remote_repo),
+                topics_column(remote_repo),
+            ])
+        })
+        .collect();
+    print_table(
+        &["REPO", "DIRTY", "UNPUSHED", "FETCHED", "LANGUAGE", "TOPICS"],
+        &rows,
+    );
+}
+
+// reposync list or reposync status.
+pub async fn run(
+    command: &str,
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    github_team_prefix: &String,
+    language: Option<&String>,
+) {
+    let remote_repos = provider::list_remote_repos(provider).await;
+    let local_repos = list_local_repos(repo_root_dir);
+    let directories = state::load().directories;
+    if command == "list" {
+        list(
+            &local_repos,
+            &remote_repos,
+            github_team_prefix,
+            &directories,
+            language,
+        );
+    } else {
+        status(
+            &local_repos,
+            &remote_repos,
+            github_team_prefix,
+            &directories,
+            language,
+        );
+    }
+}
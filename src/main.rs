@@ -1,15 +1,73 @@
 use clap::Arg;
+use clap::ArgAction;
 use clap::value_parser;
-use indicatif::MultiProgress;
-use indicatif::ProgressBar;
-use indicatif::ProgressStyle;
 use reqwest::Url;
 use std::path::PathBuf;
+use std::time::Duration;
+mod adopt;
+mod api;
+mod archive;
+mod branches;
+mod bundle;
+mod cache;
+mod cancel;
+mod children;
+mod clipboard;
+mod config;
+mod daemon;
+mod digest;
+mod disk_space;
+mod duplicates;
+mod duration;
+mod email;
+mod events;
+mod failure_log;
+mod find;
 mod git;
-use git::{LocalRepo, RemoteRepo, list_local_repos};
-use tokio::task::JoinHandle;
+mod git_version;
+mod github_actions;
+mod grep;
+mod heal;
+mod health;
+mod ignore;
+mod init;
+mod inventory;
+mod last_run;
+mod licenses;
+mod list;
+mod open;
+mod owners;
+mod paths;
+mod plugin;
+mod progress;
+mod prompt;
+mod provider;
+mod push;
+mod queue;
+mod read_only;
+mod remotes;
+mod rename;
+mod repo_lock;
+mod report;
+mod report_diff;
+mod rules;
+mod sample;
+mod scaffold;
+mod script;
+mod selftest;
+mod snapshot;
+mod ssh;
+mod stale;
+mod state;
+mod style;
+mod submodules;
+mod sync;
+mod time_format;
+mod transferred;
+mod views;
+mod webhook;
 
-fn parse_command_line_arguments() -> clap::ArgMatches {
+fn command() -> clap::Command {
     clap::Command::new("reposync")
         .about("tool to keep team repos up to date.")
         .version(env!("CARGO_PKG_VERSION"))
@@ -31,6 +89,13 @@ fn parse_command_line_arguments() -> clap::ArgMatches {
                 .value_parser(value_parser!(PathBuf))
                 .help("It has to point to the directory with all repos."),
         )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .env("REPOSYNC_CONFIG")
+                .value_parser(value_parser!(PathBuf))
+                .help("Config file, defaults to config.toml in the reposync config dir, e.g. ~/.config/reposync."),
+        )
         .arg(
             Arg::new("github_token")
                 .short('t')
@@ -48,225 +113,1243 @@ fn parse_command_line_arguments() -> clap::ArgMatches {
                 .required(true)
                 .help("e.g. [team_] When cloning this prefix would be removed. If your team does not use it, set it to empty."),
         )
-        .get_matches()
-}
-
-#[derive(Debug)]
-enum State {
-    CloneError,
-    PullError,
-    Updated,
-    Cloned,
-    PullNoOp,
-}
-
-#[derive(Debug)]
-struct GitResponse {
-    name: String,
-    message: String,
-    state: State,
-}
-
-fn handle_new_pull(local_repo: LocalRepo, progress_bar: ProgressBar) -> JoinHandle<GitResponse> {
-    let handle = tokio::task::spawn_blocking(move || {
-        let response = git::git_pull(local_repo.clone());
-        progress_bar.inc(1);
-        let _ = match response {
-            Err(message) => {
-                return GitResponse {
-                    name: local_repo.name,
-                    message: message.to_string(),
-                    state: State::PullError,
-                };
-            }
-            Ok(output) => {
-                let error_message = String::from_utf8_lossy(output.stderr.trim_ascii()).to_string();
-                let info_message = String::from_utf8_lossy(output.stdout.trim_ascii()).to_string();
-
-                // Any non-zero exit from git pull is an error (e.g. unresolved conflicts).
-                if !output.status.success() {
-                    return GitResponse {
-                        name: local_repo.name,
-                        message: format!(
-                            "Error message: {}\nInfo message: {}",
-                            error_message, info_message
+        .arg(
+            Arg::new("accept_hostkeys")
+                .long("accept-hostkeys")
+                .action(ArgAction::SetTrue)
+                .help("Adds GitHub's published SSH host keys to ~/.ssh/known_hosts if they are missing."),
+        )
+        .arg(
+            Arg::new("ascii")
+                .long("ascii")
+                .action(ArgAction::SetTrue)
+                .help("Plain-text output without spinners or colors, e.g. for screen readers and dumb terminals."),
+        )
+        .arg(
+            Arg::new("utc")
+                .long("utc")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("iso")
+                .help("Prints times as UTC timestamps instead of relative ones like 3 days ago, and durations in ISO 8601."),
+        )
+        .arg(
+            Arg::new("iso")
+                .long("iso")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Prints times as ISO 8601 timestamps instead of relative ones like 3 days ago, and durations in ISO 8601."),
+        )
+        .arg(
+            Arg::new("progress")
+                .long("progress")
+                .env("REPOSYNC_PROGRESS")
+                .default_value("auto")
+                .value_parser(["auto", "bars", "lines", "none"])
+                .help("How syncing shows progress: bars, one line per finished repo like [12/300] pull foo: updated, or nothing but the summary. auto uses bars on a terminal and lines otherwise."),
+        )
+        .arg(
+            Arg::new("fail_fast")
+                .long("fail-fast")
+                .action(ArgAction::SetTrue)
+                .help("Cancels the remaining clones and pulls on the first error and exits with a non-zero code."),
+        )
+        .arg(
+            Arg::new("fail_on")
+                .long("fail-on")
+                .env("REPOSYNC_FAIL_ON")
+                .value_parser(["error", "warning"])
+                .help("Exits with a non-zero code when a repo failed, or with warning also when a repo only has a warning like local changes, a shallow clone or a branch other than the default one. For strict CI runs."),
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .env("REPOSYNC_COMPACT")
+                .action(ArgAction::SetTrue)
+                .help("Sums up up-to-date, updated, cloned and skipped repos and warnings in one line like 212 up to date; 3 updated: a, b, c. Errors are still shown in full."),
+        )
+        .arg(
+            Arg::new("add_safe_directory")
+                .long("add-safe-directory")
+                .env("REPOSYNC_ADD_SAFE_DIRECTORY")
+                .action(ArgAction::SetTrue)
+                .help("Adds the repos in the repo root dir that are owned by another user to git's global safe.directory, one entry per repo. For shared servers."),
+        )
+        .arg(
+            Arg::new("read_only")
+                .long("read-only")
+                .env("REPOSYNC_READ_ONLY")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["accept_hostkeys", "credential_helper", "add_safe_directory"])
+                .help("Changes nothing: no clones, pulls, renames or writes to GitHub, the state or the config. A sync only reports what it would clone and pull, commands that change something are refused. For shared mirrors."),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .env("REPOSYNC_FORCE")
+                .action(ArgAction::SetTrue)
+                .help("Clones new repos even when their size reported by the API exceeds the free disk space, with a warning instead of aborting."),
+        )
+        .arg(
+            Arg::new("show_diffstat")
+                .long("show-diffstat")
+                .action(ArgAction::SetTrue)
+                .help("Shows the diffstat of the incoming changes for each updated repo."),
+        )
+        .arg(
+            Arg::new("copy_failures")
+                .long("copy-failures")
+                .env("REPOSYNC_COPY_FAILURES")
+                .action(ArgAction::SetTrue)
+                .help("Copies a Markdown list of the failed repos with the relevant git error line to the clipboard, ready for the team chat."),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .env("REPOSYNC_OUTPUT")
+                .default_value("text")
+                .value_parser(["text", "github-actions"])
+                .help("With github-actions also prints failed repos as ::error:: and repos with warnings as ::warning:: workflow commands, so they show up as annotations on the run, and appends a summary to GITHUB_STEP_SUMMARY."),
+        )
+        .arg(
+            Arg::new("status_file")
+                .long("status-file")
+                .env("REPOSYNC_STATUS_FILE")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Where every sync writes the counts, duration and failures of the run as JSON for shell prompts and status bars, defaults to last_run.json in the reposync state dir, e.g. ~/.local/state/reposync."),
+        )
+        .arg(
+            Arg::new("events")
+                .long("events")
+                .env("REPOSYNC_EVENTS")
+                .action(ArgAction::SetTrue)
+                .help("Appends updated, cloned and archived repos to the event feed read by the events subcommand. Always on in daemon mode."),
+        )
+        .arg(
+            Arg::new("email")
+                .long("email")
+                .env("REPOSYNC_EMAIL")
+                .action(ArgAction::SetTrue)
+                .help("Emails the summary to the recipients of the email table in the config file, with only_failures only when repos failed. Always on in daemon mode. Needs the email feature."),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .env("REPOSYNC_REPORT")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Writes the result of the sync with git version, reposync version, OS, config profile and effective settings to FILE, as JSON if it ends in .json, as a standalone web page with durations if it ends in .html, otherwise as Markdown. A json:, html: or markdown: prefix like html:out.html picks the format explicitly."),
+        )
+        .arg(
+            Arg::new("prune")
+                .long("prune")
+                .env("REPOSYNC_PRUNE")
+                .action(ArgAction::SetTrue)
+                .help("Removes remote-tracking branches that no longer exist on the remote while pulling."),
+        )
+        .arg(
+            Arg::new("shallow_since")
+                .long("shallow-since")
+                .env("REPOSYNC_SHALLOW_SINCE")
+                .value_name("DURATION")
+                .value_parser(duration::parse_duration)
+                .help("Clones new repos only with the history of the given time window, e.g. 6mo. Repos without commits in the window get their last commit."),
+        )
+        .arg(
+            Arg::new("reference_dir")
+                .long("reference-dir")
+                .env("REPOSYNC_REFERENCE_DIR")
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .help("Local object cache with mirrors like <repo>.git. New clones borrow objects from the mirror of their repo instead of downloading them. The clones depend on the cache, only prune it with reposync cache gc."),
+        )
+        .arg(
+            Arg::new("quick")
+                .long("quick")
+                .env("REPOSYNC_QUICK")
+                .action(ArgAction::SetTrue)
+                .help("Skips repos without a push on GitHub since their last sync and pulls the others only when the HEAD of origin differs from the local one. Pushes to other branches than the default one are only picked up by a full sync."),
+        )
+        .arg(
+            Arg::new("ssh_multiplex")
+                .long("ssh-multiplex")
+                .env("REPOSYNC_SSH_MULTIPLEX")
+                .action(ArgAction::SetTrue)
+                .help("Opens one SSH connection per host before syncing and shares it between all git processes, instead of a handshake per repo. Only with --protocol ssh."),
+        )
+        .arg(
+            Arg::new("deepen_on_demand")
+                .long("deepen-on-demand")
+                .env("REPOSYNC_DEEPEN_ON_DEMAND")
+                .action(ArgAction::SetTrue)
+                .help("Fetches the full history of a shallow clone when a pull fails for lack of older commits, then pulls again."),
+        )
+        .arg(
+            Arg::new("prune_tags")
+                .long("prune-tags")
+                .env("REPOSYNC_PRUNE_TAGS")
+                .action(ArgAction::SetTrue)
+                .help("Like --prune, but also removes local tags that no longer exist on the remote."),
+        )
+        .arg(
+            Arg::new("verify_signatures")
+                .long("verify-signatures")
+                .env("REPOSYNC_VERIFY_SIGNATURES")
+                .action(ArgAction::SetTrue)
+                .help("Verifies the signature of HEAD after each pull and reports repos whose tip is not signed by a trusted key."),
+        )
+        .arg(
+            Arg::new("allowed_signers")
+                .long("allowed-signers")
+                .env("REPOSYNC_ALLOWED_SIGNERS")
+                .value_parser(value_parser!(PathBuf))
+                .help("ssh allowed signers file used by --verify-signatures. Defaults to gpg.ssh.allowedSignersFile from the git config."),
+        )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .env("REPOSYNC_VERIFY")
+                .action(ArgAction::SetTrue)
+                .help("Runs git fsck after each clone and reports corrupt repos."),
+        )
+        .arg(
+            Arg::new("verify_pulls")
+                .long("verify-pulls")
+                .env("REPOSYNC_VERIFY_PULLS")
+                .action(ArgAction::SetTrue)
+                .help("Runs git fsck after each pull as well. Slow on big repos."),
+        )
+        .arg(
+            Arg::new("heal")
+                .long("heal")
+                .env("REPOSYNC_HEAL")
+                .action(ArgAction::SetTrue)
+                .help(format!("Moves corrupt repos into {} in the repo root dir, clones them again and copies dirty files back.", heal::QUARANTINE_DIR)),
+        )
+        .arg(
+            Arg::new("api_budget")
+                .long("api-budget")
+                .env("REPOSYNC_API_BUDGET")
+                .default_value("1000")
+                .value_parser(value_parser!(u32))
+                .help("Maximum number of GitHub API requests per hour."),
+        )
+        .arg(
+            Arg::new("api_concurrency")
+                .long("api-concurrency")
+                .env("REPOSYNC_API_CONCURRENCY")
+                .default_value("4")
+                .value_parser(value_parser!(usize))
+                .help("Maximum number of concurrent GitHub API requests."),
+        )
+        .arg(
+            Arg::new("http_connect_timeout")
+                .long("http-connect-timeout")
+                .env("REPOSYNC_HTTP_CONNECT_TIMEOUT")
+                .value_parser(duration::parse_duration)
+                .help("Timeout for connecting to the GitHub API, e.g. 10s."),
+        )
+        .arg(
+            Arg::new("http_timeout")
+                .long("http-timeout")
+                .env("REPOSYNC_HTTP_TIMEOUT")
+                .value_parser(duration::parse_duration)
+                .help("Timeout for a whole GitHub API request, e.g. 30s."),
+        )
+        .arg(
+            Arg::new("http_pool_size")
+                .long("http-pool-size")
+                .env("REPOSYNC_HTTP_POOL_SIZE")
+                .value_parser(value_parser!(usize))
+                .help("Maximum idle connections kept open to the GitHub API."),
+        )
+        .arg(
+            Arg::new("http2")
+                .long("http2")
+                .env("REPOSYNC_HTTP2")
+                .action(ArgAction::SetTrue)
+                .help("Allows HTTP/2 for the GitHub API. Without it only HTTP/1.1 is used."),
+        )
+        .arg(
+            Arg::new("user_agent_suffix")
+                .long("user-agent-suffix")
+                .env("REPOSYNC_USER_AGENT_SUFFIX")
+                .help("Appended to the reposync user agent, e.g. for proxies that filter by user agent."),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_name("FIXTURES")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with("replay")
+                .help("Writes all GitHub API responses of this run to a JSON file, e.g. fixtures.json."),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("FIXTURES")
+                .value_parser(value_parser!(PathBuf))
+                .help("Answers GitHub API requests from a file written by --record instead of the network."),
+        )
+        .arg(
+            Arg::new("provider")
+                .long("provider")
+                .env("REPOSYNC_PROVIDER")
+                .default_value("github")
+                .help("Where team repos come from. Any other name than github runs the reposync-provider-<name> plugin."),
+        )
+        .arg(
+            Arg::new("action")
+                .long("action")
+                .action(ArgAction::Append)
+                .help("Runs the reposync-action-<name> plugin after syncing with the outcome of every repo. Can be repeated."),
+        )
+        .arg(
+            Arg::new("classify_script")
+                .long("classify-script")
+                .env("REPOSYNC_CLASSIFY_SCRIPT")
+                .value_parser(value_parser!(PathBuf))
+                .help("Lua script whose classify(repo) function can return {skip = true}, {dir = \"name\"} or {clone_args = {...}} for new repos. Needs the lua feature."),
+        )
+        .arg(
+            Arg::new("archive_action")
+                .long("archive-action")
+                .env("REPOSYNC_ARCHIVE_ACTION")
+                .default_value("report")
+                .value_parser(["report", "readonly", "move", "delete"])
+                .help(format!("What happens to local clones of archived team repos. readonly disables pushing and adds an {} file, move puts them into {} in the repo root dir, delete only removes clean clones without unpushed commits or stashes.", archive::ARCHIVED_MARKER, archive::ARCHIVE_DIR)),
+        )
+        .arg(
+            Arg::new("archive_grace_period")
+                .long("archive-grace-period")
+                .env("REPOSYNC_ARCHIVE_GRACE_PERIOD")
+                .default_value("0d")
+                .value_parser(duration::parse_duration)
+                .help("Archived repos are only acted on once they were archived longer than this, e.g. 30d."),
+        )
+        .arg(
+            Arg::new("clone_timeout")
+                .long("clone-timeout")
+                .env("REPOSYNC_CLONE_TIMEOUT")
+                .value_parser(duration::parse_duration)
+                .help("Aborts clones that take longer than this, e.g. 10m. Partial clones are removed."),
+        )
+        .arg(
+            Arg::new("deadline")
+                .long("deadline")
+                .env("REPOSYNC_DEADLINE")
+                .value_parser(duration::parse_duration)
+                .help("Starts no clone or pull that is expected to run past this time after the start, e.g. 5m. Running ones finish, the others are reported as deferred and synced next time."),
+        )
+        .arg(
+            Arg::new("protocol")
+                .long("protocol")
+                .env("REPOSYNC_PROTOCOL")
+                .default_value("ssh")
+                .value_parser(["ssh", "https"])
+                .help("Protocol used for cloning new repos."),
+        )
+        .arg(
+            Arg::new("credential_helper")
+                .long("credential-helper")
+                .help("Configures this git credential.helper globally if none is set, e.g. manager or osxkeychain. Only used with --protocol https."),
+        )
+        .arg(
+            Arg::new("token_credentials")
+                .long("token-credentials")
+                .env("REPOSYNC_TOKEN_CREDENTIALS")
+                .action(ArgAction::SetTrue)
+                .help("Answers https credential requests of git with the github token for this run only. Only used with --protocol https."),
+        )
+        .arg(
+            Arg::new("language")
+                .long("language")
+                .global(true)
+                .help("Only repos with this GitHub language, e.g. rust. Applies to syncing, list and status."),
+        )
+        .arg(
+            Arg::new("jobs")
+                .long("jobs")
+                .env("REPOSYNC_JOBS")
+                .default_value("16")
+                .value_parser(value_parser!(usize))
+                .help("Maximum number of repos pulled or cloned at the same time."),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("sample")
+                .help("Syncs at most N repos, local repos first, each phase by name. Useful to try flags on a few repos."),
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Syncs at most N randomly picked repos, local repos first."),
+        )
+        .arg(
+            Arg::new("adopt")
+                .long("adopt")
+                .action(ArgAction::SetTrue)
+                .help("Offers to adopt existing clones whose origin is a new team repo instead of cloning it again."),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .action(ArgAction::SetTrue)
+                .help("Asks before cloning each new team repo, which clone to keep of duplicates and what to do with clones of repos moved out of the team. Declined repos are ignored in future runs."),
+        )
+        .subcommand(
+            clap::Command::new("sync")
+                .about("Syncs only the given repos, e.g. to retry failed ones. Without a subcommand all repos are synced.")
+                .arg(
+                    Arg::new("repos")
+                        .value_name("REPO")
+                        .num_args(1..)
+                        .required(true)
+                        .help("Local directory or team repo names."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("digest")
+                .about("Lists the commits that landed on the default branches of all local repos.")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .default_value("24h")
+                        .value_parser(duration::parse_duration)
+                        .help("Time window, e.g. 24h, 7d or 2w."),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .default_value("text")
+                        .value_parser(["text", "markdown"])
+                        .help("Output format."),
+                )
+                .arg(
+                    Arg::new("author")
+                        .long("author")
+                        .action(ArgAction::Append)
+                        .help("Only commits by this author, matched case-insensitively by name. Can be repeated."),
+                )
+                .arg(
+                    Arg::new("group_by")
+                        .long("group-by")
+                        .default_value("repo")
+                        .value_parser(["repo", "author"])
+                        .help("Groups commits by repo or by author."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("branches")
+                .about("Lists local branches across all repos whose upstream is gone or whose last commit is old.")
+                .arg(
+                    Arg::new("stale")
+                        .long("stale")
+                        .default_value("90d")
+                        .value_parser(duration::parse_duration)
+                        .help("Branches without commits in this time window are stale, e.g. 90d."),
+                )
+                .arg(
+                    Arg::new("delete")
+                        .long("delete")
+                        .action(ArgAction::SetTrue)
+                        .help("Asks for confirmation and deletes all listed branches."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("branch")
+                .about("Creates or checks out the same branch in several repos.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("create")
+                        .about("Creates BRANCH and switches to it.")
+                        .arg(Arg::new("branch").value_name("BRANCH").required(true))
+                        .arg(
+                            Arg::new("repos")
+                                .value_name("REPO")
+                                .num_args(1..)
+                                .required(true)
+                                .help("Team repo or directory names."),
+                        )
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .help("Start point of the branch, e.g. origin/main. Defaults to HEAD."),
                         ),
-                        state: State::PullError,
-                    };
-                }
-
-                if info_message == "Already up to date" || info_message == "Already up to date." {
-                    return GitResponse {
-                        name: local_repo.name,
-                        message: "".into(),
-                        state: State::PullNoOp,
-                    };
-                }
-
-                return GitResponse {
-                    name: local_repo.name,
-                    message: info_message,
-                    state: State::Updated,
-                };
-            }
-        };
-    });
-    return handle;
+                )
+                .subcommand(
+                    clap::Command::new("checkout")
+                        .about("Switches to BRANCH, creating a tracking branch if it only exists on origin.")
+                        .arg(Arg::new("branch").value_name("BRANCH").required(true))
+                        .arg(
+                            Arg::new("repos")
+                                .value_name("REPO")
+                                .num_args(1..)
+                                .required(true)
+                                .help("Team repo or directory names."),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("archive")
+                .about("Archives a team repo on GitHub after confirmation and applies --archive-action to its local clone.")
+                .arg(
+                    Arg::new("repo")
+                        .value_name("REPO")
+                        .required(true)
+                        .help("Team repo or directory name."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("open")
+                .about("Opens the GitHub page of a team repo in the browser.")
+                .arg(
+                    Arg::new("repo")
+                        .value_name("REPO")
+                        .required(true)
+                        .help("Team repo name or local directory name."),
+                )
+                .arg(
+                    Arg::new("prs")
+                        .long("prs")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("ci")
+                        .help("Opens the pull requests instead."),
+                )
+                .arg(
+                    Arg::new("ci")
+                        .long("ci")
+                        .action(ArgAction::SetTrue)
+                        .help("Opens the GitHub Actions runs instead."),
+                )
+                .arg(
+                    Arg::new("print")
+                        .long("print")
+                        .action(ArgAction::SetTrue)
+                        .help("Prints the url instead of opening it, e.g. for a remote shell."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("rename")
+                .about("Renames a team repo on GitHub after confirmation, then its local clone and origin url.")
+                .arg(
+                    Arg::new("old")
+                        .value_name("OLD")
+                        .required(true)
+                        .help("Team repo or directory name."),
+                )
+                .arg(
+                    Arg::new("new")
+                        .value_name("NEW")
+                        .required(true)
+                        .help("New repo name, the team prefix is added if it is missing."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("new")
+                .about("Creates a team repo from a template repo on GitHub, adds it to the team and clones it.")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .help("Repo name, the team prefix is added if it is missing."),
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .required(true)
+                        .help("Template repo as owner/repo."),
+                )
+                .arg(
+                    Arg::new("owner")
+                        .long("owner")
+                        .help("Organization of the new repo. Defaults to the owner of the template."),
+                )
+                .arg(
+                    Arg::new("public")
+                        .long("public")
+                        .action(ArgAction::SetTrue)
+                        .help("Creates a public repo instead of a private one."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("push")
+                .about("Pushes the current branch of the given repos if it has an upstream, is ahead of it and is not the default branch.")
+                .arg(
+                    Arg::new("repos")
+                        .value_name("REPO")
+                        .num_args(1..)
+                        .required(true)
+                        .help("Team repo or directory names."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("remotes")
+                .about("Manages the origin remotes of all local team repos.")
+                .arg(
+                    Arg::new("convert")
+                        .long("convert")
+                        .required(true)
+                        .value_parser(["ssh", "https"])
+                        .help("Rewrites origin to the ssh or https url of the team repo."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("list")
+                .about("Lists all team repos with their local directory, language and topics."),
+        )
+        .subcommand(
+            clap::Command::new("status")
+                .about("Shows dirty files and unpushed commits of all local team repos."),
+        )
+        .subcommand(
+            clap::Command::new("ignore")
+                .about("Manages team repos that are never proposed as new. Lists them without a subcommand.")
+                .subcommand(
+                    clap::Command::new("add").arg(
+                        Arg::new("repos")
+                            .value_name("REPO")
+                            .num_args(1..)
+                            .required(true)
+                            .help("Team repo or directory names."),
+                    ),
+                )
+                .subcommand(
+                    clap::Command::new("remove").arg(
+                        Arg::new("repos")
+                            .value_name("REPO")
+                            .num_args(1..)
+                            .required(true)
+                            .help("Team repo or directory names."),
+                    ),
+                )
+                .subcommand(clap::Command::new("list")),
+        )
+        .subcommand(
+            clap::Command::new("views")
+                .about(format!("Regenerates {}/ in the repo root dir with symlinks to the repos grouped by language, topic and custom tag. Once created, views are regenerated after each sync.", views::VIEWS_DIR))
+                .subcommand(
+                    clap::Command::new("tag")
+                        .about("Adds repos to a custom tag.")
+                        .arg(Arg::new("tag").value_name("TAG").required(true))
+                        .arg(
+                            Arg::new("repos")
+                                .value_name("REPO")
+                                .num_args(1..)
+                                .required(true)
+                                .help("Team repo or directory names."),
+                        ),
+                )
+                .subcommand(
+                    clap::Command::new("untag")
+                        .about("Removes repos from a custom tag.")
+                        .arg(Arg::new("tag").value_name("TAG").required(true))
+                        .arg(
+                            Arg::new("repos")
+                                .value_name("REPO")
+                                .num_args(1..)
+                                .required(true)
+                                .help("Team repo or directory names."),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("find")
+                .about("Lists the repos that contain all --file and none of the --not-file paths.")
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .action(ArgAction::Append)
+                        .help("Path relative to the repo root that has to exist, e.g. Dockerfile. Can be repeated."),
+                )
+                .arg(
+                    Arg::new("not_file")
+                        .long("not-file")
+                        .action(ArgAction::Append)
+                        .help("Path relative to the repo root that must not exist. Can be repeated."),
+                )
+                .group(
+                    clap::ArgGroup::new("paths")
+                        .args(["file", "not_file"])
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("stale")
+                .about("Lists the team repos without a push in the given time window, oldest first.")
+                .arg(
+                    Arg::new("older_than")
+                        .long("older-than")
+                        .default_value("1y")
+                        .value_parser(duration::parse_duration)
+                        .help("Repos without pushes in this time window are stale, e.g. 6w or 1y."),
+                )
+                .arg(
+                    Arg::new("open_prs")
+                        .long("open-prs")
+                        .action(ArgAction::SetTrue)
+                        .help("Adds the number of open pull requests of each stale repo, one API request per repo."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("licenses")
+                .about("Lists the team repos per license as detected by GitHub and the repos without one.")
+                .arg(
+                    Arg::new("fail_on_missing_license")
+                        .long("fail-on-missing-license")
+                        .action(ArgAction::SetTrue)
+                        .help("Exits with 1 if any team repo has no license, e.g. in compliance pipelines."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("owners")
+                .about("Lists the owners of all repos from their CODEOWNERS file and the repos without owners.")
+                .arg(
+                    Arg::new("owner")
+                        .long("owner")
+                        .help("Only repos owned by this team or user, e.g. @my-org/my-team."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("inventory")
+                .about("Lists the dependencies in Cargo.toml, package.json and go.mod of all repos with the repos using each version.")
+                .arg(
+                    Arg::new("dependency")
+                        .long("dependency")
+                        .help("Only this dependency, e.g. serde."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("grep")
+                .about("Searches the tracked files of all repos with git grep. Exits with 1 when nothing matched.")
+                .arg(Arg::new("pattern").value_name("PATTERN").required(true))
+                .arg(
+                    Arg::new("ignore_case")
+                        .short('i')
+                        .long("ignore-case")
+                        .action(ArgAction::SetTrue)
+                        .help("Ignores case differences."),
+                )
+                .arg(
+                    Arg::new("fixed_strings")
+                        .short('F')
+                        .long("fixed-strings")
+                        .action(ArgAction::SetTrue)
+                        .help("Matches PATTERN literally instead of as a regular expression."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("daemon")
+                .about("Syncs on every tick with the options given before daemon and records the event feed.")
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .default_value("15m")
+                        .value_parser(duration::parse_duration)
+                        .help("Time between the end of a sync and the start of the next one, e.g. 15m. Waking up from sleep syncs right away."),
+                )
+                .arg(
+                    Arg::new("active_hours")
+                        .long("active-hours")
+                        .env("REPOSYNC_ACTIVE_HOURS")
+                        .value_parser(daemon::parse_time_range)
+                        .help("Only syncs in this local time window, e.g. 07:00-19:00."),
+                )
+                .arg(
+                    Arg::new("active_days")
+                        .long("active-days")
+                        .env("REPOSYNC_ACTIVE_DAYS")
+                        .value_parser(daemon::parse_days)
+                        .help("Only syncs on these days, e.g. mon-fri or sat,sun."),
+                )
+                .arg(
+                    Arg::new("quiet_hours")
+                        .long("quiet-hours")
+                        .env("REPOSYNC_QUIET_HOURS")
+                        .value_parser(daemon::parse_time_range)
+                        .help("Never syncs in this local time window, it may wrap past midnight, e.g. 22:00-06:00."),
+                )
+                .arg(
+                    Arg::new("webhook_listen")
+                        .long("webhook-listen")
+                        .env("REPOSYNC_WEBHOOK_LISTEN")
+                        .value_parser(value_parser!(std::net::SocketAddr))
+                        .help("Accepts GitHub org webhooks on this address, e.g. 127.0.0.1:8787, and syncs repos on push, creation, archiving and renames right away."),
+                )
+                .arg(
+                    Arg::new("webhook_secret")
+                        .long("webhook-secret")
+                        .env("REPOSYNC_WEBHOOK_SECRET")
+                        .hide_env_values(true)
+                        .help("Secret of the GitHub webhook, deliveries without a matching signature are rejected."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("init")
+                .about("Asks for provider, organization, team, token source, repo root dir and prefix, checks them against the API and writes them as defaults to the config file."),
+        )
+        .subcommand(
+            clap::Command::new("selftest")
+                .about("Syncs fake team repos from bare repos in a temp dir through a stub API and checks the result, without touching GitHub or the repo root dir.")
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .action(ArgAction::SetTrue)
+                        .help("Keeps the temp dir with the fake remotes and clones for a closer look."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("events")
+                .about("Prints the feed of updated, cloned and archived repos recorded by syncs with --events.")
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .short('f')
+                        .action(ArgAction::SetTrue)
+                        .help("Keeps printing new events as they are recorded."),
+                )
+                .arg(
+                    Arg::new("repo")
+                        .long("repo")
+                        .action(ArgAction::Append)
+                        .help("Only events of repos matching this pattern, * matches anything, e.g. orders-*. Can be repeated."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("prompt-status")
+                .about("Prints a short segment like ⇣3 ⇡1 ✖1 for shell prompts from the status file of the last sync: repos left behind their remote, repos with local commits on the default branch and failed repos. Prints nothing when there is nothing to look at, and never touches the network or git."),
+        )
+        .subcommand(
+            clap::Command::new("snapshot")
+                .about("Records and restores the HEAD commit of every repo in the repo root dir. Lists snapshots without a subcommand.")
+                .subcommand(
+                    clap::Command::new("save")
+                        .about("Records the HEAD commits as NAME, replacing an older snapshot of that name.")
+                        .arg(Arg::new("name").value_name("NAME").required(true)),
+                )
+                .subcommand(
+                    clap::Command::new("restore")
+                        .about("Checks out the commits of snapshot NAME, detached unless --branch is given. Repos with local changes are skipped.")
+                        .arg(Arg::new("name").value_name("NAME").required(true))
+                        .arg(
+                            Arg::new("branch")
+                                .long("branch")
+                                .help("Creates or resets this branch to the commit in every repo."),
+                        ),
+                )
+                .subcommand(clap::Command::new("list")),
+        )
+        .subcommand(
+            clap::Command::new("bundle")
+                .about("Shares the team setup: settings without the token, repo list and directory layout.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("export")
+                        .about("Writes the bundle of this machine to FILE.")
+                        .arg(Arg::new("file").value_name("FILE").required(true).value_parser(value_parser!(PathBuf))),
+                )
+                .subcommand(
+                    clap::Command::new("import")
+                        .about("Clones the repos of the bundle in FILE, takes over ignored repos, tags and directory names and prints the shared settings.")
+                        .arg(Arg::new("file").value_name("FILE").required(true).value_parser(value_parser!(PathBuf))),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("report")
+                .about("Works with the reports written by --report. The last 10 are also kept as JSON in the reposync state dir.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("diff")
+                        .about("Lists repos that newly fail, recovered, appeared or were removed between two JSON reports. Exits non-zero when repos newly fail.")
+                        .arg(
+                            Arg::new("reports")
+                                .value_name("REPORT")
+                                .num_args(0..=2)
+                                .value_parser(value_parser!(PathBuf))
+                                .help("The older and the newer report, or only the newer one with --against."),
+                        )
+                        .arg(
+                            Arg::new("against")
+                                .long("against")
+                                .value_parser(["last"])
+                                .help("Compares REPORT with the newest kept report that differs from it, or the last two kept reports without REPORT."),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("cache")
+                .about("Maintains the object cache of --reference-dir.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("update")
+                        .about("Creates a mirror <repo>.git for every active team repo without one and fetches into the others."),
+                )
+                .subcommand(
+                    clap::Command::new("gc")
+                        .about("Removes mirrors of repos that are gone or archived and compacts the others. Mirrors that clones in the repo root dir borrow from are kept."),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("submodules")
+                .about("Maintains meta repos that bundle team repos as submodules.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("sync")
+                        .about("Points every submodule that is a team repo at the current HEAD of its default branch and stages the change.")
+                        .arg(
+                            Arg::new("meta_repo")
+                                .value_name("META_REPO")
+                                .required(true)
+                                .value_parser(value_parser!(PathBuf))
+                                .help("Directory in the repo root dir or path of the meta repo."),
+                        )
+                        .arg(
+                            Arg::new("commit")
+                                .long("commit")
+                                .action(ArgAction::SetTrue)
+                                .help("Commits the updated pointers instead of only staging them."),
+                        ),
+                ),
+        )
 }
 
-fn handle_new_clone(
-    repo_root_dir: &PathBuf,
-    github_team_prefix: &String,
-    new_repo: RemoteRepo,
-    progress_bar: ProgressBar,
-) -> JoinHandle<GitResponse> {
-    let repo_root_dir_clone = repo_root_dir.clone();
-    let github_team_prefix_clone = github_team_prefix.clone();
-
-    let handle = tokio::task::spawn_blocking(move || {
-        let result = git::git_clone(
-            &new_repo.clone(),
-            repo_root_dir_clone,
-            github_team_prefix_clone,
-        );
-        progress_bar.inc(1);
-        let _ = match result {
-            Ok(_) => {
-                return GitResponse {
-                    name: new_repo.name,
-                    message: "".into(),
-                    state: State::Cloned,
-                };
+// the config file can replace defaults on this machine, so it is read before the real parse.
+fn parse_command_line_arguments() -> (clap::ArgMatches, config::Config) {
+    let pre_parsed = command().ignore_errors(true).try_get_matches().ok();
+    let config_path = pre_parsed
+        .as_ref()
+        .and_then(|cli| cli.get_one::<PathBuf>("config").cloned());
+    let is_init = pre_parsed
+        .as_ref()
+        .is_some_and(|cli| cli.subcommand_name() == Some("init"));
+    // prompt-status only reads the status file and runs in every shell prompt.
+    let is_prompt_status = pre_parsed
+        .as_ref()
+        .is_some_and(|cli| cli.subcommand_name() == Some("prompt-status"));
+    let config = match config::load(config_path.as_ref()) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}: {}", style::paint("invalid config", style::ERROR), e);
+            std::process::exit(1);
+        }
+    };
+    let mut parser = command();
+    // init writes the defaults for the required flags, so it has to run without them.
+    if is_init || is_prompt_status {
+        for id in [
+            "github_team_repo_url",
+            "repo_root_dir",
+            "github_token",
+            "github_team_prefix",
+        ] {
+            parser = parser.mut_arg(id, |arg| arg.required(false));
+        }
+    }
+    let defaults = &config.defaults;
+    if let Some(provider) = &defaults.provider {
+        parser = parser.mut_arg("provider", |arg| arg.default_value(provider.clone()));
+    }
+    if let Some(github_team_repo_url) = &defaults.github_team_repo_url {
+        parser = parser.mut_arg("github_team_repo_url", |arg| {
+            arg.default_value(github_team_repo_url.clone())
+                .required(false)
+        });
+    }
+    if let Some(repo_root_dir) = &defaults.repo_root_dir {
+        parser = parser.mut_arg("repo_root_dir", |arg| {
+            arg.default_value(repo_root_dir.as_os_str().to_owned())
+                .required(false)
+        });
+    }
+    if let Some(github_team_prefix) = &defaults.github_team_prefix {
+        parser = parser.mut_arg("github_team_prefix", |arg| {
+            arg.default_value(github_team_prefix.clone())
+                .required(false)
+        });
+    }
+    // the command only runs when neither the flag nor GITHUB_TOKEN gives a token.
+    if let Some(token_command) = &defaults.token_command
+        && !is_init
+        && !is_prompt_status
+        && pre_parsed
+            .as_ref()
+            .is_some_and(|cli| cli.get_one::<String>("github_token").is_none())
+    {
+        match config::token_from_command(token_command) {
+            Ok(token) => {
+                parser = parser.mut_arg("github_token", |arg| {
+                    arg.default_value(token)
+                        .hide_default_value(true)
+                        .required(false)
+                });
             }
-            Err(message) => {
-                return GitResponse {
-                    name: new_repo.name,
-                    message: message.to_string(),
-                    state: State::CloneError,
-                };
+            Err(e) => {
+                println!(
+                    "{}: {}",
+                    style::paint("token command failed", style::ERROR),
+                    e
+                );
+                std::process::exit(1);
             }
-        };
-    });
-    return handle;
+        }
+    }
+    if let Some((_, machine)) = config.machine_overrides() {
+        if let Some(repo_root_dir) = &machine.repo_root_dir {
+            parser = parser.mut_arg("repo_root_dir", |arg| {
+                arg.default_value(repo_root_dir.as_os_str().to_owned())
+                    .required(false)
+            });
+        }
+        if let Some(jobs) = machine.jobs {
+            parser = parser.mut_arg("jobs", |arg| arg.default_value(jobs.to_string()));
+        }
+        if let Some(language) = &machine.language {
+            parser = parser.mut_arg("language", |arg| arg.default_value(language.clone()));
+        }
+    }
+    (parser.get_matches(), config)
 }
 
 #[tokio::main]
 async fn main() {
-    let cli = parse_command_line_arguments();
+    let (cli, config) = parse_command_line_arguments();
+    // before anything is printed, errors of the checks below included.
+    style::set_ascii(cli.get_flag("ascii"));
+    read_only::set_read_only(cli.get_flag("read_only"));
+    if let Err(e) = read_only::check_command(&cli) {
+        println!("{}", style::paint(&e, style::ERROR));
+        std::process::exit(1);
+    }
+    if let Err(e) = git_version::check_flags(&cli) {
+        println!("{}", style::paint(&e, style::ERROR));
+        std::process::exit(1);
+    }
+    if let Err(e) = rules::set_rules(&config.classify) {
+        println!(
+            "{}: {}",
+            style::paint("invalid classify rule", style::ERROR),
+            e
+        );
+        std::process::exit(1);
+    }
+    if let Err(e) = style::set_theme(&config.colors) {
+        println!("{}: {}", style::paint("invalid colors", style::ERROR), e);
+        std::process::exit(1);
+    }
+
+    // both work without the required flags, e.g. before the first init.
+    match cli.subcommand() {
+        Some(("prompt-status", _)) => return last_run::run(cli.get_one::<PathBuf>("status_file")),
+        Some(("init", _)) => return init::run(cli.get_one::<PathBuf>("config")).await,
+        _ => {}
+    }
 
     let repo_root_dir = cli.get_one::<PathBuf>("repo_root_dir").unwrap();
     let token = cli.get_one::<String>("github_token").unwrap();
     let github_team_repo_url = cli.get_one::<Url>("github_team_repo_url").unwrap();
     let github_team_prefix = cli.get_one::<String>("github_team_prefix").unwrap();
-
-    let multi_progress_bar = MultiProgress::new();
-    let spinner_style =
-        ProgressStyle::with_template("{prefix:.bold.dim} {pos:>7}/{len:7}").unwrap();
-
-    let mut clone_threads: Vec<JoinHandle<GitResponse>> = Vec::new();
-    let mut pull_threads: Vec<JoinHandle<GitResponse>> = Vec::new();
-
-    let pull_progress_bar = multi_progress_bar.add(ProgressBar::no_length());
-    pull_progress_bar.set_style(spinner_style.clone());
-    pull_progress_bar.set_prefix(format!("gathering local repos..."));
-    let local_repos = list_local_repos(&repo_root_dir);
-    pull_progress_bar.set_prefix(format!("pulling repos..."));
-    pull_progress_bar.set_length(local_repos.len() as u64);
-    for local_repo in local_repos.clone() {
-        pull_threads.push(handle_new_pull(local_repo, pull_progress_bar.clone()));
+    let directories = state::load().directories;
+    git::set_remote_names(
+        config.remote_name.as_deref().unwrap_or("origin"),
+        config.remote_names(github_team_prefix, &directories),
+    );
+    let http_options = api::HttpOptions {
+        connect_timeout: cli.get_one::<Duration>("http_connect_timeout").copied(),
+        timeout: cli.get_one::<Duration>("http_timeout").copied(),
+        pool_size: cli.get_one::<usize>("http_pool_size").copied(),
+        http2: cli.get_flag("http2"),
+        user_agent_suffix: cli.get_one::<String>("user_agent_suffix").cloned(),
+    };
+    let mut api_client = match api::ApiClient::new(
+        token.clone(),
+        *cli.get_one::<u32>("api_budget").unwrap(),
+        *cli.get_one::<usize>("api_concurrency").unwrap(),
+        &http_options,
+    ) {
+        Ok(api_client) => api_client,
+        Err(e) => {
+            println!(
+                "{}: {}",
+                style::paint("failed to create the http client", style::ERROR),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Some(path) = cli.get_one::<PathBuf>("record") {
+        api_client.record(path.clone());
     }
-
-    let clone_progress_bar = multi_progress_bar.add(ProgressBar::no_length());
-    clone_progress_bar.set_style(spinner_style.clone());
-    clone_progress_bar.set_prefix("looking for new team repos...");
-    let remote_repos = git::get_all_repos(token, github_team_prefix, github_team_repo_url).await;
-    let github_active_team_repos = git::list_active_github_team_repos(remote_repos.clone()).await;
-    let new_repos =
-        git::find_new_repos(&github_active_team_repos, &local_repos, &github_team_prefix);
-    clone_progress_bar.set_prefix("cloning team repos...");
-    clone_progress_bar.set_length(new_repos.len() as u64);
-    for new_repo in new_repos.clone() {
-        clone_threads.push(handle_new_clone(
-            repo_root_dir,
+    if let Some(path) = cli.get_one::<PathBuf>("replay")
+        && let Err(e) = api_client.replay(path)
+    {
+        println!(
+            "{}: {}",
+            style::paint(&format!("failed to read {}", path.display()), style::ERROR),
+            e
+        );
+        std::process::exit(1);
+    }
+    let provider_name = cli.get_one::<String>("provider").unwrap();
+    let mut provider: Box<dyn provider::Provider + Sync + '_> = match provider_name.as_str() {
+        "github" => Box::new(provider::GitHub {
+            api_client: &api_client,
             github_team_prefix,
-            new_repo,
-            clone_progress_bar.clone(),
-        ));
+            github_team_repo_url,
+        }),
+        name => Box::new(provider::External {
+            name: name.to_string(),
+            github_team_prefix,
+        }),
+    };
+    if !config.source.is_empty() || !config.external.is_empty() {
+        provider = Box::new(provider::Mixed {
+            main: provider,
+            main_label: provider_name,
+            api_client: &api_client,
+            sources: &config.source,
+            external: &config.external,
+        });
     }
+    progress::set_mode(cli.get_one::<String>("progress").unwrap());
+    time_format::set_format(if cli.get_flag("iso") {
+        time_format::TimeFormat::Iso
+    } else if cli.get_flag("utc") {
+        time_format::TimeFormat::Utc
+    } else {
+        time_format::TimeFormat::Relative
+    });
 
-    let github_archived_team_repos =
-        git::list_archived_github_team_repos(remote_repos.clone()).await;
-    let archived_repos = git::find_archived_local_repos(
-        &github_archived_team_repos,
-        &local_repos,
-        &github_team_prefix,
-    );
-
-    let mut pull_errors: Vec<GitResponse> = Vec::new();
-    let mut pull_noop: Vec<GitResponse> = Vec::new();
-    let mut updated: Vec<GitResponse> = Vec::new();
-    let mut cloned: Vec<GitResponse> = Vec::new();
-    let mut clone_errors: Vec<GitResponse> = Vec::new();
-    for pull_thread in pull_threads {
-        let pull_result = pull_thread.await.unwrap();
-        match pull_result.state {
-            State::PullError => {
-                pull_errors.push(pull_result);
-            }
-            State::PullNoOp => {
-                pull_noop.push(pull_result);
-            }
-            State::Updated => {
-                updated.push(pull_result);
-            }
-            _ => {
-                panic!("Unexpected state in pull thread: {:?}", pull_result);
+    let provider = provider.as_ref();
+    let jobs = *cli.get_one::<usize>("jobs").unwrap();
+    let protocol = cli.get_one::<String>("protocol").unwrap();
+    match cli.subcommand() {
+        Some(("digest", digest_args)) => digest::run(repo_root_dir, digest_args),
+        Some(("branches", branches_args)) => branches::run(repo_root_dir, branches_args),
+        Some(("branch", branch_args)) => {
+            if !branches::switch(repo_root_dir, github_team_prefix, branch_args) {
+                std::process::exit(1);
             }
-        };
-    }
-    pull_progress_bar.set_message("pulling finished");
-    pull_progress_bar.finish_and_clear();
-
-    for clone_thread in clone_threads {
-        let clone_result = clone_thread.await.unwrap();
-        match clone_result.state {
-            State::CloneError => {
-                clone_errors.push(clone_result);
+        }
+        Some(("archive", archive_args)) => {
+            archive::run(
+                &cli,
+                provider,
+                &api_client,
+                github_team_repo_url,
+                github_team_prefix,
+                repo_root_dir,
+                archive_args,
+            )
+            .await
+        }
+        Some(("open", open_args)) => open::run(provider, github_team_prefix, open_args).await,
+        Some(("rename", rename_args)) => {
+            rename::run(
+                provider,
+                &api_client,
+                github_team_repo_url,
+                github_team_prefix,
+                repo_root_dir,
+                rename_args,
+            )
+            .await
+        }
+        Some(("new", new_args)) => {
+            scaffold::run(
+                &api_client,
+                github_team_repo_url,
+                github_team_prefix,
+                repo_root_dir,
+                protocol,
+                new_args,
+            )
+            .await
+        }
+        Some(("push", push_args)) => {
+            if !push::run(repo_root_dir, github_team_prefix, push_args) {
+                std::process::exit(1);
             }
-            State::Cloned => {
-                cloned.push(clone_result);
+        }
+        Some(("find", find_args)) => find::run(repo_root_dir, find_args, jobs).await,
+        Some(("stale", stale_args)) => {
+            stale::run(provider, &api_client, github_team_repo_url, stale_args).await
+        }
+        Some(("licenses", licenses_args)) => licenses::run(provider, licenses_args).await,
+        Some(("owners", owners_args)) => owners::run(repo_root_dir, owners_args),
+        Some(("inventory", inventory_args)) => inventory::run(repo_root_dir, inventory_args),
+        Some(("grep", grep_args)) => {
+            if !grep::run(repo_root_dir, grep_args, jobs).await {
+                std::process::exit(1);
             }
-            _ => {
-                panic!("Unexpected state in clone thread: {:?}", clone_result);
+        }
+        Some(("daemon", daemon_args)) => {
+            daemon::run(repo_root_dir, github_team_prefix, daemon_args).await
+        }
+        Some(("selftest", selftest_args)) => {
+            if !selftest::run(selftest_args).await {
+                std::process::exit(1);
             }
-        };
-    }
-    clone_progress_bar.set_message("cloning finished");
-    clone_progress_bar.finish_and_clear();
-
-    println!(
-        "\x1b[32mPull no-op count\x1b[0m: {}",
-        pull_noop.iter().count()
-    );
-    for updated_repo in updated {
-        println!("\x1b[33m{}\x1b[0m: updated", updated_repo.name);
-    }
-    for cloned_repo in cloned {
-        println!("\x1b[33m{}\x1b[0m: cloned", cloned_repo.name);
-    }
-    for archived_repo in archived_repos {
-        println!("\x1b[33m{}\x1b[0m: archived", archived_repo.name);
-    }
-    for clone_error in clone_errors {
-        println!("\x1b[31m{}\x1b[0m: failed to clone:", clone_error.name);
-        for line in clone_error.message.lines() {
-            println!("  {}", line);
         }
-    }
-    for pull_error in pull_errors {
-        println!("\x1b[31m{}\x1b[0m: failed to pull:", pull_error.name);
-        for line in pull_error.message.lines() {
-            println!("  {}", line);
+        Some(("events", events_args)) => events::run(events_args).await,
+        Some(("report", report_args)) => report_diff::run(report_args),
+        Some(("snapshot", snapshot_args)) => snapshot::run(repo_root_dir, snapshot_args).await,
+        Some(("ignore", ignore_args)) => ignore::run(ignore_args),
+        Some((command @ ("list" | "status"), _)) => {
+            list::run(
+                command,
+                provider,
+                repo_root_dir,
+                github_team_prefix,
+                cli.get_one::<String>("language"),
+            )
+            .await
+        }
+        Some(("views", views_args)) => {
+            views::run(provider, repo_root_dir, github_team_prefix, views_args).await
+        }
+        Some(("bundle", bundle_args)) => {
+            bundle::run(
+                &cli,
+                provider,
+                repo_root_dir,
+                github_team_prefix,
+                bundle_args,
+            )
+            .await
+        }
+        Some(("remotes", remotes_args)) => {
+            remotes::run(provider, repo_root_dir, github_team_prefix, remotes_args).await
+        }
+        // everything below clones or fetches.
+        subcommand => {
+            sync::configure_transport(&cli, token);
+            match subcommand {
+                Some(("cache", cache_args)) => {
+                    cache::run(&cli, provider, repo_root_dir, cache_args).await
+                }
+                Some(("submodules", submodules_args)) => {
+                    submodules::run(provider, repo_root_dir, protocol, submodules_args).await
+                }
+                _ => {
+                    sync::run(
+                        &cli,
+                        &config,
+                        provider,
+                        &api_client,
+                        github_team_repo_url,
+                        github_team_prefix,
+                        repo_root_dir,
+                    )
+                    .await
+                }
+            }
         }
     }
 }
@@ -0,0 +1,94 @@
+use std::process::Command;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::RemoteRepo;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::style;
+
+// the GitHub page from the API payload, derived from the clone url for providers without one.
+pub fn web_url(remote_repo: &RemoteRepo) -> String {
+    remote_repo
+        .html_url
+        .clone()
+        .unwrap_or_else(|| format!("https://{}", git::normalize_url(&remote_repo.clone_url)))
+}
+
+// $BROWSER wins, otherwise whatever the OS opens urls with.
+fn browser_command(url: &str) -> Command {
+    if let Some(browser) = std::env::var_os("BROWSER") {
+        let mut command = Command::new(browser);
+        command.arg(url);
+        return command;
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg("start").arg("");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url);
+    command
+}
+
+// page is empty for the repo itself, or e.g. pulls or actions.
+fn open_page(
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    name: &str,
+    page: &str,
+    print_only: bool,
+) -> Result<String, String> {
+    let directories = state::load().directories;
+    let Some(remote_repo) = remote_repos.iter().find(|remote_repo| {
+        remote_repo.name == name
+            || git::local_dir_name(remote_repo, github_team_prefix, &directories) == name
+    }) else {
+        return Err(format!("{} is no team repo", name));
+    };
+    let mut url = web_url(remote_repo);
+    if !page.is_empty() {
+        url = format!("{}/{}", url.trim_end_matches('/'), page);
+    }
+    if print_only {
+        return Ok(url);
+    }
+    let status = browser_command(&url)
+        .status()
+        .map_err(|e| format!("failed to open {}: {}", url, e))?;
+    if !status.success() {
+        return Err(format!("failed to open {}: {}", url, status));
+    }
+    Ok(url)
+}
+
+pub async fn run(provider: &dyn Provider, github_team_prefix: &String, open_args: &ArgMatches) {
+    let name = open_args.get_one::<String>("repo").unwrap();
+    let page = if open_args.get_flag("prs") {
+        "pulls"
+    } else if open_args.get_flag("ci") {
+        "actions"
+    } else {
+        ""
+    };
+    let remote_repos = provider::list_remote_repos(provider).await;
+    match open_page(
+        &remote_repos,
+        github_team_prefix,
+        name,
+        page,
+        open_args.get_flag("print"),
+    ) {
+        Ok(url) => println!("{}", url),
+        Err(e) => {
+            println!("{}: {}", style::paint(name, style::ERROR), e);
+            std::process::exit(1);
+        }
+    }
+}
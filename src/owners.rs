@@ -0,0 +1,102 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git::LocalRepo;
+use crate::git::list_local_repos;
+use crate::style;
+
+// the places GitHub looks for CODEOWNERS, the first one found wins.
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+#[derive(Debug)]
+pub struct RepoOwners {
+    pub repo: String,
+    pub owners: BTreeSet<String>,
+}
+
+// every owner named by any rule, e.g. @org/team or a user; comments and empty lines are skipped.
+fn parse_codeowners(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .flat_map(|line| line.split_whitespace().skip(1))
+        .map(|owner| owner.to_string())
+        .collect()
+}
+
+pub fn collect_owners(local_repos: &Vec<LocalRepo>) -> Vec<RepoOwners> {
+    local_repos
+        .iter()
+        .map(|local_repo| RepoOwners {
+            repo: local_repo.name.clone(),
+            owners: CODEOWNERS_PATHS
+                .iter()
+                .find_map(|path| fs::read_to_string(local_repo.path.join(path)).ok())
+                .map(|content| parse_codeowners(&content))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+// owners are compared ignoring case and the leading @, as GitHub does.
+fn is_same_owner(a: &str, b: &str) -> bool {
+    a.trim_start_matches('@')
+        .eq_ignore_ascii_case(b.trim_start_matches('@'))
+}
+
+pub fn print(repo_owners: &Vec<RepoOwners>, owner: Option<&String>) {
+    let mut unowned: Vec<&str> = Vec::new();
+    let mut owned = 0;
+    for repo in repo_owners {
+        if repo.owners.is_empty() {
+            unowned.push(&repo.repo);
+            continue;
+        }
+        match owner {
+            Some(owner) => {
+                if repo
+                    .owners
+                    .iter()
+                    .any(|candidate| is_same_owner(candidate, owner))
+                {
+                    owned += 1;
+                    println!("{}", repo.repo);
+                }
+            }
+            None => {
+                owned += 1;
+                let owners: Vec<&str> = repo.owners.iter().map(|owner| owner.as_str()).collect();
+                println!("{}: {}", repo.repo, owners.join(" "));
+            }
+        }
+    }
+    match owner {
+        Some(owner) => println!(
+            "{}: {}",
+            style::paint(&format!("Owned by {}", owner), style::OK),
+            owned
+        ),
+        None => println!("{}: {}", style::paint("Owned", style::OK), owned),
+    }
+    if !unowned.is_empty() {
+        println!(
+            "{}: {}",
+            style::paint("Without CODEOWNERS", style::WARNING),
+            unowned.len()
+        );
+        for repo in unowned {
+            println!("{}", repo);
+        }
+    }
+}
+
+pub fn run(repo_root_dir: &PathBuf, owners_args: &ArgMatches) {
+    print(
+        &collect_owners(&list_local_repos(repo_root_dir)),
+        owners_args.get_one::<String>("owner"),
+    );
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+// per-user directory for logs and state, e.g. ~/.local/state/reposync.
+pub fn state_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("reposync"));
+    }
+    if let Some(dir) = std::env::var_os("XDG_STATE_HOME") {
+        return Some(PathBuf::from(dir).join("reposync"));
+    }
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("reposync")
+    })
+}
+
+// per-user directory for the config file, e.g. ~/.config/reposync.
+pub fn config_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("reposync"));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("reposync"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("reposync"))
+}
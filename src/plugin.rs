@@ -0,0 +1,60 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+use serde::Serialize;
+
+// plugins are executables on the PATH, e.g. reposync-provider-gitea or reposync-action-notify.
+// they get one JSON request on stdin and answer on stdout, stderr is passed through.
+pub fn call(kind: &str, name: &str, request: &impl Serialize) -> Result<String, String> {
+    let binary = format!("reposync-{}-{}", kind, name);
+    let request = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("failed to start {}: {}", binary, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(request.as_bytes())
+            .map_err(|e| format!("failed to write to {}: {}", binary, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", binary, output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncedRepo {
+    pub name: String,
+    pub state: String,
+    // how long the clone or pull took.
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ActionRequest<'a> {
+    command: &'a str,
+    repo_root_dir: &'a str,
+    repos: &'a Vec<SyncedRepo>,
+}
+
+// post-sync actions get the outcome of every repo of the run, their stdout is printed as is.
+pub fn run_action(
+    name: &str,
+    repo_root_dir: &str,
+    repos: &Vec<SyncedRepo>,
+) -> Result<String, String> {
+    call(
+        "action",
+        name,
+        &ActionRequest {
+            command: "synced",
+            repo_root_dir,
+            repos,
+        },
+    )
+}
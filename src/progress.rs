@@ -0,0 +1,35 @@
+use std::io::IsTerminal;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+use crate::style;
+
+// bars redraw in place, which tmux panes and log files handle badly. lines print one
+// line per finished repo instead, none leaves only the summary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Bars,
+    Lines,
+    None,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(Mode::Bars as u8);
+
+// auto draws bars on a terminal and falls back to lines for pipes and --ascii.
+pub fn set_mode(value: &str) {
+    let mode = match value {
+        "bars" if !style::is_ascii() => Mode::Bars,
+        "none" => Mode::None,
+        "auto" if !style::is_ascii() && std::io::stderr().is_terminal() => Mode::Bars,
+        _ => Mode::Lines,
+    };
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn mode() -> Mode {
+    match MODE.load(Ordering::Relaxed) {
+        value if value == Mode::Lines as u8 => Mode::Lines,
+        value if value == Mode::None as u8 => Mode::None,
+        _ => Mode::Bars,
+    }
+}
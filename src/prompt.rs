@@ -0,0 +1,47 @@
+use std::io::Write;
+
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+// the answer, or the default for an empty answer.
+pub fn ask(question: &str, default: &str) -> String {
+    if default.is_empty() {
+        print!("{} ", question);
+    } else {
+        print!("{} [{}] ", question, default);
+    }
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return default.to_string();
+    }
+    match answer.trim() {
+        "" => default.to_string(),
+        answer => answer.to_string(),
+    }
+}
+
+// 1-based choice between the options, anything else is no choice.
+pub fn choose(question: &str, options: &[&str]) -> Option<usize> {
+    println!("{}", question);
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}) {}", index + 1, option);
+    }
+    print!("[1-{}, empty skips] ", options.len());
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return None;
+    }
+    match answer.trim().parse::<usize>() {
+        Ok(choice) if choice >= 1 && choice <= options.len() => Some(choice - 1),
+        _ => None,
+    }
+}
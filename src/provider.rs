@@ -0,0 +1,196 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::config;
+use crate::git;
+use crate::git::RemoteRepo;
+use crate::plugin;
+use crate::style;
+
+pub type RepoList<'a> = Pin<Box<dyn Future<Output = Result<Vec<RemoteRepo>, String>> + Send + 'a>>;
+
+// where team repos come from, GitHub or an in-house git hosting via a plugin.
+pub trait Provider {
+    fn list_repos(&self) -> RepoList<'_>;
+}
+
+// commands go on without team repos when listing them fails, the error is printed.
+pub async fn list_remote_repos(provider: &dyn Provider) -> Vec<RemoteRepo> {
+    match provider.list_repos().await {
+        Ok(remote_repos) => remote_repos,
+        Err(e) => {
+            println!(
+                "{}: {}",
+                style::paint("failed to list team repos", style::ERROR),
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+pub struct GitHub<'a> {
+    pub api_client: &'a ApiClient,
+    pub github_team_prefix: &'a String,
+    pub github_team_repo_url: &'a Url,
+}
+
+impl Provider for GitHub<'_> {
+    fn list_repos(&self) -> RepoList<'_> {
+        Box::pin(async move {
+            api::get_all_repos(
+                self.api_client,
+                self.github_team_prefix,
+                self.github_team_repo_url,
+            )
+            .await
+        })
+    }
+}
+
+// spawns reposync-provider-<name>, which answers with a JSON array of repos in the GitHub API shape.
+pub struct External<'a> {
+    pub name: String,
+    pub github_team_prefix: &'a String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListReposRequest<'a> {
+    command: &'a str,
+    team_prefix: &'a str,
+}
+
+impl Provider for External<'_> {
+    fn list_repos(&self) -> RepoList<'_> {
+        Box::pin(async move {
+            let name = self.name.clone();
+            let team_prefix = self.github_team_prefix.clone();
+            let response = tokio::task::spawn_blocking(move || {
+                plugin::call(
+                    "provider",
+                    &name,
+                    &ListReposRequest {
+                        command: "list_repos",
+                        team_prefix: &team_prefix,
+                    },
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+            serde_json::from_str(&response).map_err(|e| e.to_string())
+        })
+    }
+}
+
+// the source of the external repos of the config file in the summary.
+const EXTERNAL_SOURCE: &str = "external";
+
+// the repos of --provider plus those of the sources and the external repos in the config
+// file. a repo listed by more than one source is taken from the first one.
+pub struct Mixed<'a> {
+    pub main: Box<dyn Provider + Sync + 'a>,
+    pub main_label: &'a str,
+    pub api_client: &'a ApiClient,
+    pub sources: &'a [config::Source],
+    pub external: &'a [config::ExternalRepo],
+}
+
+impl Mixed<'_> {
+    async fn list_source(&self, source: &config::Source) -> Result<Vec<RemoteRepo>, String> {
+        match source.provider.as_str() {
+            "github" => {
+                let url = source
+                    .github_team_repo_url
+                    .as_deref()
+                    .ok_or("github_team_repo_url is missing".to_string())?
+                    .parse::<Url>()
+                    .map_err(|e| e.to_string())?;
+                api::get_all_repos(self.api_client, &source.prefix, &url).await
+            }
+            "static" => Ok(source
+                .repos
+                .iter()
+                .map(|repo| RemoteRepo {
+                    name: repo.name.clone(),
+                    ssh_url: repo.url.clone(),
+                    clone_url: repo.url.clone(),
+                    ..RemoteRepo::default()
+                })
+                .collect()),
+            name => {
+                External {
+                    name: name.to_string(),
+                    github_team_prefix: &source.prefix,
+                }
+                .list_repos()
+                .await
+            }
+        }
+    }
+}
+
+fn matches_filters(source: &config::Source, repo: &RemoteRepo) -> bool {
+    source
+        .language
+        .as_ref()
+        .is_none_or(|language| repo.has_language(language))
+        && (source.topics.is_empty()
+            || source
+                .topics
+                .iter()
+                .any(|topic| repo.topics.contains(topic)))
+}
+
+impl Provider for Mixed<'_> {
+    fn list_repos(&self) -> RepoList<'_> {
+        Box::pin(async move {
+            let mut repos = self.main.list_repos().await?;
+            for repo in &mut repos {
+                repo.source = Some(self.main_label.to_string());
+            }
+            for source in self.sources {
+                let source_repos = self
+                    .list_source(source)
+                    .await
+                    .map_err(|e| format!("source {}: {}", source.label(), e))?;
+                for mut repo in source_repos {
+                    if !matches_filters(source, &repo)
+                        || repos.iter().any(|known| known.name == repo.name)
+                    {
+                        continue;
+                    }
+                    let dir_name = repo
+                        .name
+                        .strip_prefix(source.prefix.as_str())
+                        .unwrap_or(&repo.name)
+                        .to_string();
+                    repo.dir_name = Some(dir_name);
+                    repo.source = Some(source.label().to_string());
+                    repos.push(repo);
+                }
+            }
+            for external_repo in self.external {
+                let name = external_repo.dir_name();
+                if repos.iter().any(|known| {
+                    known.name == name || git::is_same_remote(&external_repo.url, known)
+                }) {
+                    continue;
+                }
+                repos.push(RemoteRepo {
+                    name: name.clone(),
+                    ssh_url: external_repo.url.clone(),
+                    clone_url: external_repo.url.clone(),
+                    dir_name: Some(name),
+                    source: Some(EXTERNAL_SOURCE.to_string()),
+                    ..RemoteRepo::default()
+                });
+            }
+            Ok(repos)
+        })
+    }
+}
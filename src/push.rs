@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::branches;
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::list_local_repos;
+use crate::style;
+
+// why the current branch of a repo is not pushed, None if it is safe to push.
+fn skip_reason(local_repo: &LocalRepo) -> Option<String> {
+    let Some(branch) = git::current_branch(&local_repo.path) else {
+        return Some("detached HEAD".to_string());
+    };
+    let default_branch = git::default_branch_ref(&local_repo.path);
+    if git::remote_branch(&local_repo.path, &default_branch).unwrap_or(&default_branch) == branch {
+        return Some(format!("{} is the default branch", branch));
+    }
+    match git::ahead_of_upstream(&local_repo.path) {
+        None => Some(format!("{} has no upstream", branch)),
+        Some(0) => Some(format!("{} is not ahead of its upstream", branch)),
+        Some(_) => None,
+    }
+}
+
+// pushes the current branch of the given repos, returns false if any push failed.
+pub fn run(repo_root_dir: &PathBuf, github_team_prefix: &String, push_args: &ArgMatches) -> bool {
+    let names: Vec<String> = push_args
+        .get_many::<String>("repos")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let local_repos = list_local_repos(repo_root_dir);
+    let mut pushed: Vec<String> = Vec::new();
+    let mut skipped: Vec<(String, String)> = Vec::new();
+    let mut errors: Vec<(String, String)> = Vec::new();
+    for local_repo in branches::find_repos(&local_repos, &names, github_team_prefix) {
+        let local_repo = match local_repo {
+            Ok(local_repo) => local_repo,
+            Err(name) => {
+                errors.push((name, "not found".to_string()));
+                continue;
+            }
+        };
+        if let Some(reason) = skip_reason(local_repo) {
+            skipped.push((local_repo.name.clone(), reason));
+            continue;
+        }
+        match git::git_push(&local_repo.path) {
+            Ok(output) if output.status.success() => pushed.push(local_repo.name.clone()),
+            Ok(output) => errors.push((
+                local_repo.name.clone(),
+                String::from_utf8_lossy(output.stderr.trim_ascii()).to_string(),
+            )),
+            Err(e) => errors.push((local_repo.name.clone(), e.to_string())),
+        }
+    }
+
+    // same layout as the sync summary, errors last.
+    println!("{}: {}", style::paint("Pushed", style::OK), pushed.len());
+    for name in &pushed {
+        println!("{}: pushed", style::paint(name, style::WARNING));
+    }
+    for (name, reason) in &skipped {
+        println!(
+            "{}: skipped, {}",
+            style::paint(name, style::WARNING),
+            reason
+        );
+    }
+    for (name, error) in &errors {
+        println!("{}: failed to push:", style::paint(name, style::ERROR));
+        for line in error.lines() {
+            println!("  {}", line);
+        }
+    }
+    errors.is_empty()
+}
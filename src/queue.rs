@@ -0,0 +1,55 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc;
+
+type Task<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+// runs at most max_in_flight tasks at once, starting them in push order,
+// and hands out their results in completion order.
+pub struct WorkQueue<T> {
+    tasks: mpsc::UnboundedSender<Task<T>>,
+    results: mpsc::UnboundedReceiver<T>,
+    pending: usize,
+}
+
+impl<T: Send + 'static> WorkQueue<T> {
+    pub fn new(max_in_flight: usize) -> WorkQueue<T> {
+        let (tasks, mut queued_tasks) = mpsc::unbounded_channel::<Task<T>>();
+        let (result_sender, results) = mpsc::unbounded_channel();
+        let permits = Arc::new(Semaphore::new(max_in_flight.max(1)));
+        // a task is only spawned once a slot is free, so queued tasks hold no git process or progress bar.
+        tokio::spawn(async move {
+            while let Some(task) = queued_tasks.recv().await {
+                let permit = permits.clone().acquire_owned().await.unwrap();
+                let result_sender = result_sender.clone();
+                tokio::spawn(async move {
+                    let _ = result_sender.send(task.await);
+                    drop(permit);
+                });
+            }
+        });
+        WorkQueue {
+            tasks,
+            results,
+            pending: 0,
+        }
+    }
+
+    pub fn push(&mut self, task: impl Future<Output = T> + Send + 'static) {
+        self.pending += 1;
+        let _ = self.tasks.send(Box::pin(task));
+    }
+
+    // None once all pushed tasks have delivered their result.
+    pub async fn next(&mut self) -> Option<T> {
+        if self.pending == 0 {
+            return None;
+        }
+        let result = self.results.recv().await;
+        self.pending -= 1;
+        result
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use clap::ArgMatches;
+
+// subcommands that change repos, GitHub, the state or the config, by their path.
+const MUTATING_COMMANDS: [&str; 16] = [
+    "archive",
+    "branch",
+    "bundle import",
+    "cache",
+    "daemon",
+    "ignore add",
+    "ignore remove",
+    "init",
+    "new",
+    "push",
+    "remotes",
+    "rename",
+    "snapshot restore",
+    "snapshot save",
+    "submodules",
+    "views",
+];
+
+// otherwise read-only subcommands that change something with a flag, by path and flag id.
+const MUTATING_FLAGS: [(&str, &str); 1] = [("branches", "delete")];
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+// e.g. "ignore add" for reposync ignore add foo, with the arguments of the last subcommand.
+fn command_path(cli: &ArgMatches) -> (String, &ArgMatches) {
+    let mut names = Vec::new();
+    let mut matches = cli;
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        names.push(name);
+        matches = sub_matches;
+    }
+    (names.join(" "), matches)
+}
+
+// refuses the whole command up front, so nothing runs halfway before a mutation is refused.
+pub fn check_command(cli: &ArgMatches) -> Result<(), String> {
+    if !is_read_only() {
+        return Ok(());
+    }
+    let (path, args) = command_path(cli);
+    if let Some(command) = MUTATING_COMMANDS
+        .iter()
+        .find(|command| path == **command || path.starts_with(&format!("{} ", command)))
+    {
+        return Err(format!("{} is not possible with --read-only", command));
+    }
+    if let Some((command, flag)) = MUTATING_FLAGS
+        .iter()
+        .find(|(command, flag)| path == *command && args.get_flag(flag))
+    {
+        return Err(format!(
+            "{} --{} is not possible with --read-only",
+            command, flag
+        ));
+    }
+    // a read-only sync stops before it pulls or clones, there is no result to report on.
+    let report_flags = [
+        ("--report", cli.contains_id("report")),
+        ("--status-file", cli.contains_id("status_file")),
+        (
+            "--output github-actions",
+            cli.get_one::<String>("output").map(String::as_str) == Some("github-actions"),
+        ),
+        ("--copy-failures", cli.get_flag("copy_failures")),
+        ("--email", cli.get_flag("email")),
+    ];
+    match report_flags.iter().find(|(_, given)| *given) {
+        Some((flag, _)) if path.is_empty() || path == "sync" => Err(format!(
+            "{} is not possible with --read-only, a read-only sync has no result to report",
+            flag
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::style;
+
+// rewrites the team remote of every local team repo to the ssh or https url from the API.
+pub fn convert(
+    local_repos: &Vec<LocalRepo>,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    protocol: &str,
+    directories: &BTreeMap<String, String>,
+) {
+    let mut unchanged = 0;
+    for remote_repo in remote_repos {
+        let Some(local_repo) =
+            git::find_local_repo(remote_repo, local_repos, github_team_prefix, directories)
+        else {
+            continue;
+        };
+        let url = remote_repo.url(protocol);
+        if git::remote_url(&local_repo.path).as_ref() == Some(url) {
+            unchanged += 1;
+            continue;
+        }
+        match git::set_remote_url(&local_repo.path, url) {
+            Ok(output) if output.status.success() => {
+                println!(
+                    "{}: {} set to {}",
+                    style::paint(&local_repo.name, style::WARNING),
+                    git::remote_name(&local_repo.path),
+                    url
+                )
+            }
+            Ok(output) => println!(
+                "{}: failed to set origin: {}",
+                style::paint(&local_repo.name, style::ERROR),
+                String::from_utf8_lossy(output.stderr.trim_ascii())
+            ),
+            Err(e) => println!(
+                "{}: failed to set origin: {}",
+                style::paint(&local_repo.name, style::ERROR),
+                e
+            ),
+        }
+    }
+    println!(
+        "{}: {}",
+        style::paint("Already using the preferred protocol", style::OK),
+        unchanged
+    );
+}
+
+pub async fn run(
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    github_team_prefix: &String,
+    remotes_args: &ArgMatches,
+) {
+    let protocol = remotes_args.get_one::<String>("convert").unwrap();
+    let remote_repos = provider::list_remote_repos(provider).await;
+    convert(
+        &list_local_repos(repo_root_dir),
+        &remote_repos,
+        github_team_prefix,
+        protocol,
+        &state::load().directories,
+    );
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use reqwest::Url;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::git;
+use crate::git::RemoteRepo;
+use crate::git::list_local_repos;
+use crate::prompt;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::style;
+
+// renames a team repo on GitHub, then its clone and origin, and remembers the old names.
+async fn rename_repo(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    remote_repos: &Vec<RemoteRepo>,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let mut sync_state = state::load();
+    let Some(old_repo) = remote_repos.iter().find(|remote_repo| {
+        remote_repo.name == old_name
+            || git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                == old_name
+    }) else {
+        return Err(format!("{} is no team repo", old_name));
+    };
+    let new_name = if new_name.starts_with(github_team_prefix.as_str()) {
+        new_name.to_string()
+    } else {
+        format!("{}{}", github_team_prefix, new_name)
+    };
+    let full_name = old_repo
+        .full_name()
+        .ok_or(format!("no owner in {}", old_repo.clone_url))?;
+    if !prompt::confirm(&format!("rename {} to {} on GitHub?", full_name, new_name)) {
+        return Ok(());
+    }
+    let new_repo =
+        api::rename_repo(api_client, github_team_repo_url, &full_name, &new_name).await?;
+    println!(
+        "{}: renamed to {}",
+        style::paint(&old_repo.name, style::WARNING),
+        new_repo.name
+    );
+
+    let local_repos = list_local_repos(repo_root_dir);
+    let local_repo = git::find_local_repo(
+        old_repo,
+        &local_repos,
+        github_team_prefix,
+        &sync_state.directories,
+    );
+    // clones in a custom directory keep it, all others follow the naming convention.
+    let recorded_dir = sync_state.directories.remove(&old_repo.name);
+    let new_dir = match recorded_dir {
+        Some(dir) if dir != git::conventional_dir_name(old_repo, github_team_prefix) => dir,
+        _ => git::conventional_dir_name(&new_repo, github_team_prefix),
+    };
+    sync_state
+        .directories
+        .insert(new_repo.name.clone(), new_dir.clone());
+    sync_state.rename(&old_repo.name, &new_repo.name);
+
+    let mut result = Ok(());
+    if let Some(local_repo) = local_repo {
+        let mut path = local_repo.path.clone();
+        if new_dir != local_repo.name {
+            let new_path = repo_root_dir.join(&new_dir);
+            if new_path.exists() {
+                result = Err(format!("{} already exists", new_path.display()));
+            } else {
+                match fs::rename(&local_repo.path, &new_path) {
+                    Ok(()) => {
+                        println!(
+                            "{}: moved to {}",
+                            style::paint(&local_repo.name, style::WARNING),
+                            new_dir
+                        );
+                        sync_state.rename(&local_repo.name, &new_dir);
+                        path = new_path;
+                    }
+                    Err(e) => result = Err(format!("failed to move the clone: {}", e)),
+                }
+            }
+        }
+        // GitHub redirects the old url, but only until a new repo takes the old name.
+        let protocol = match git::remote_url(&path) {
+            Some(url) if url.starts_with("https://") => "https",
+            _ => "ssh",
+        };
+        match git::set_remote_url(&path, new_repo.url(protocol)) {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                result = Err(format!(
+                    "failed to set origin: {}",
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                ))
+            }
+            Err(e) => result = Err(format!("failed to set origin: {}", e)),
+        }
+    }
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+    result
+}
+
+pub async fn run(
+    provider: &dyn Provider,
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    rename_args: &ArgMatches,
+) {
+    let old_name = rename_args.get_one::<String>("old").unwrap();
+    let remote_repos = provider::list_remote_repos(provider).await;
+    if let Err(e) = rename_repo(
+        api_client,
+        github_team_repo_url,
+        github_team_prefix,
+        repo_root_dir,
+        &remote_repos,
+        old_name,
+        rename_args.get_one::<String>("new").unwrap(),
+    )
+    .await
+    {
+        println!("{}: {}", style::paint(old_name, style::ERROR), e);
+        std::process::exit(1);
+    }
+}
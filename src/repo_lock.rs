@@ -0,0 +1,80 @@
+use std::fs;
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::paths;
+
+// held while git works on a repo, released on drop or when the process dies.
+pub struct RepoLock {
+    _file: Option<File>,
+}
+
+// the same clone reached through a symlink or a relative root dir gets the same lock.
+// a clone that doesn't exist yet is resolved through its parent.
+fn canonical_path(repo_path: &PathBuf) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(repo_path) {
+        return path;
+    }
+    let canonical_parent =
+        repo_path
+            .parent()
+            .zip(repo_path.file_name())
+            .and_then(|(parent, name)| {
+                fs::canonicalize(parent)
+                    .ok()
+                    .map(|parent| parent.join(name))
+            });
+    canonical_parent
+        .or_else(|| std::path::absolute(repo_path).ok())
+        .unwrap_or(repo_path.clone())
+}
+
+// named by a SHA-256 of the path, so the name stays the same across processes and
+// toolchain upgrades.
+fn lock_file(repo_path: &PathBuf) -> Option<PathBuf> {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA256,
+        canonical_path(repo_path).as_os_str().as_encoded_bytes(),
+    );
+    let name: String = digest.as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    paths::state_dir().map(|dir| dir.join("locks").join(format!("{}.lock", name)))
+}
+
+#[cfg(unix)]
+fn lock_exclusive(file: &File) -> bool {
+    use std::os::fd::AsRawFd;
+    unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) == 0 }
+}
+
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &File) -> bool {
+    // there is no flock, a manual sync during a daemon sync is not serialized on Windows.
+    true
+}
+
+// serializes operations on the same repo across processes, e.g. a manual sync while the
+// daemon syncs, while different repos still run in parallel.
+// without a state dir or lock file the operation runs unlocked.
+pub async fn lock(repo_path: &PathBuf) -> RepoLock {
+    let repo_path = repo_path.clone();
+    let file = tokio::task::spawn_blocking(move || {
+        let path = lock_file(&repo_path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).ok()?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .ok()?;
+        lock_exclusive(&file).then_some(file)
+    })
+    .await
+    .ok()
+    .flatten();
+    RepoLock { _file: file }
+}
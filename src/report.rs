@@ -0,0 +1,347 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use serde::Serialize;
+
+use crate::bundle;
+use crate::config::Config;
+use crate::failure_log::FailedRepo;
+use crate::git;
+use crate::paths;
+use crate::plugin::SyncedRepo;
+
+// copies of the last reports in the state dir, for report diff --against last.
+const KEPT_REPORTS: usize = 10;
+
+// where a report comes from, so shared reports need no back-and-forth about the setup.
+#[derive(Debug, Serialize)]
+pub struct Environment {
+    pub reposync_version: String,
+    pub git_version: Option<String>,
+    pub os: String,
+    // the machine table of the config file that applies here.
+    pub profile: Option<String>,
+    // effective values by argument id, defaults included and the token left out.
+    pub settings: BTreeMap<String, String>,
+}
+
+pub fn capture_environment(cli: &ArgMatches, config: &Config) -> Environment {
+    let mut settings = BTreeMap::new();
+    let ids = ["repo_root_dir", "language"]
+        .into_iter()
+        .chain(bundle::SHARED_SETTINGS.iter().map(|(id, _)| *id));
+    for id in ids {
+        if let Some(value) = cli.get_raw(id).and_then(|mut values| values.next()) {
+            settings.insert(id.to_string(), value.to_string_lossy().into_owned());
+        }
+    }
+    Environment {
+        reposync_version: env!("CARGO_PKG_VERSION").to_string(),
+        git_version: git::git_version(),
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        profile: config.machine_overrides().map(|(name, _)| name.to_string()),
+        settings,
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    environment: &'a Environment,
+    repos: Vec<ReportedRepo<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportedRepo<'a> {
+    name: &'a str,
+    state: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<&'a str>,
+    duration_ms: u64,
+}
+
+fn table_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+fn render_markdown(report: &Report) -> String {
+    let environment = report.environment;
+    let mut markdown = String::from("# reposync report\n\n");
+    markdown.push_str(&format!("- reposync: {}\n", environment.reposync_version));
+    markdown.push_str(&format!(
+        "- git: {}\n",
+        environment.git_version.as_deref().unwrap_or("not found")
+    ));
+    markdown.push_str(&format!("- os: {}\n", environment.os));
+    markdown.push_str(&format!(
+        "- profile: {}\n",
+        environment.profile.as_deref().unwrap_or("none")
+    ));
+    markdown.push_str("\n## Settings\n\n| setting | value |\n| --- | --- |\n");
+    for (id, value) in &environment.settings {
+        markdown.push_str(&format!("| {} | {} |\n", id, table_cell(value)));
+    }
+    markdown.push_str("\n## Repos\n\n| repo | state |\n| --- | --- |\n");
+    for repo in &report.repos {
+        markdown.push_str(&format!("| {} | {} |\n", repo.name, repo.state));
+    }
+    let failures: Vec<&ReportedRepo> = report
+        .repos
+        .iter()
+        .filter(|repo| repo.output.is_some())
+        .collect();
+    if !failures.is_empty() {
+        markdown.push_str("\n## Failures\n");
+    }
+    for failure in failures {
+        markdown.push_str(&format!(
+            "\n### {} ({})\n\n```text\n{}\n```\n",
+            failure.name,
+            failure.operation.unwrap_or(""),
+            failure.output.unwrap_or("")
+        ));
+    }
+    markdown
+}
+
+pub fn kept_reports_dir() -> Result<PathBuf, String> {
+    paths::state_dir()
+        .map(|dir| dir.join("reports"))
+        .ok_or("no state directory, neither HOME nor XDG_STATE_HOME is set".to_string())
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn seconds(duration_ms: u64) -> String {
+    format!("{:.1}s", duration_ms as f64 / 1000.0)
+}
+
+// slowest repos shown in the duration chart.
+const CHARTED_REPOS: usize = 30;
+
+const HTML_STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }
+th { cursor: pointer; user-select: none; background: #f4f4f4; }
+th:hover { background: #e8e8e8; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+.failed { color: #b00020; font-weight: bold; }
+.bar { display: flex; align-items: center; gap: 0.6em; margin: 0.2em 0; }
+.bar span:first-child { width: 15em; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+.bar div { background: #4a7bd0; height: 1em; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; }
+pre { background: #f4f4f4; padding: 0.6em; overflow-x: auto; }
+";
+
+// sorts a table by the clicked column, by data-value where the text is no plain number.
+const HTML_SCRIPT: &str =
+    "document.querySelectorAll('th').forEach(th => th.addEventListener('click', () => {
+  const body = th.closest('table').tBodies[0];
+  const ascending = th.dataset.order !== 'asc';
+  th.dataset.order = ascending ? 'asc' : 'desc';
+  const value = row => row.cells[th.cellIndex].dataset.value ?? row.cells[th.cellIndex].textContent;
+  [...body.rows]
+    .sort((a, b) => {
+      const x = value(a), y = value(b);
+      const order = isNaN(x - y) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    })
+    .forEach(row => body.appendChild(row));
+}));
+";
+
+// one file without external resources, so it can be published as is, e.g. from a
+// scheduled mirror job.
+fn render_html(report: &Report) -> String {
+    let environment = report.environment;
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>reposync report</title>\n<style>\n",
+    );
+    html.push_str(HTML_STYLE);
+    html.push_str("</style>\n</head>\n<body>\n<h1>reposync report</h1>\n");
+    html.push_str("<table>\n<thead><tr><th>setting</th><th>value</th></tr></thead>\n<tbody>\n");
+    let rows = [
+        ("reposync", environment.reposync_version.as_str()),
+        (
+            "git",
+            environment.git_version.as_deref().unwrap_or("not found"),
+        ),
+        ("os", environment.os.as_str()),
+        ("profile", environment.profile.as_deref().unwrap_or("none")),
+    ]
+    .into_iter()
+    .chain(
+        environment
+            .settings
+            .iter()
+            .map(|(id, value)| (id.as_str(), value.as_str())),
+    );
+    for (setting, value) in rows {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            html_escape(setting),
+            html_escape(value)
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let mut slowest: Vec<&ReportedRepo> = report.repos.iter().collect();
+    slowest.sort_by_key(|repo| std::cmp::Reverse(repo.duration_ms));
+    slowest.truncate(CHARTED_REPOS);
+    let longest = slowest
+        .first()
+        .map(|repo| repo.duration_ms)
+        .unwrap_or(0)
+        .max(1);
+    html.push_str(&format!("<h2>Slowest {} repos</h2>\n", slowest.len()));
+    for repo in slowest {
+        html.push_str(&format!(
+            "<div class=\"bar\"><span>{}</span><div style=\"width: {:.1}%\"></div><span>{}</span></div>\n",
+            html_escape(repo.name),
+            repo.duration_ms as f64 * 60.0 / longest as f64,
+            seconds(repo.duration_ms)
+        ));
+    }
+
+    html.push_str(&format!("<h2>Repos ({})</h2>\n", report.repos.len()));
+    html.push_str(
+        "<table>\n<thead><tr><th>repo</th><th>state</th><th>duration</th></tr></thead>\n<tbody>\n",
+    );
+    for repo in &report.repos {
+        let class = if repo.output.is_some() {
+            " class=\"failed\""
+        } else {
+            ""
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td{}>{}</td><td class=\"number\" data-value=\"{}\">{}</td></tr>\n",
+            html_escape(repo.name),
+            class,
+            html_escape(repo.state),
+            repo.duration_ms,
+            seconds(repo.duration_ms)
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let failures: Vec<&ReportedRepo> = report
+        .repos
+        .iter()
+        .filter(|repo| repo.output.is_some())
+        .collect();
+    if !failures.is_empty() {
+        html.push_str(&format!("<h2>Failures ({})</h2>\n", failures.len()));
+    }
+    for failure in failures {
+        html.push_str(&format!(
+            "<details><summary><span class=\"failed\">{}</span> ({} failed)</summary>\n<pre>{}</pre>\n</details>\n",
+            html_escape(failure.name),
+            html_escape(failure.operation.unwrap_or("")),
+            html_escape(failure.output.unwrap_or(""))
+        ));
+    }
+    html.push_str("<script>\n");
+    html.push_str(HTML_SCRIPT);
+    html.push_str("</script>\n</body>\n</html>\n");
+    html
+}
+
+// oldest first, the names are timestamps.
+pub fn kept_reports() -> Vec<PathBuf> {
+    let Ok(entries) =
+        kept_reports_dir().and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    reports.sort();
+    reports
+}
+
+// as JSON whatever the format of the report, so every report can be compared later.
+fn keep(report: &Report) -> Result<(), String> {
+    let dir = kept_reports_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let path = dir.join(format!(
+        "{}.json",
+        chrono::Local::now().format("%Y-%m-%dT%H-%M-%S")
+    ));
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, json + "\n").map_err(|e| format!("{}: {}", path.display(), e))?;
+    let reports = kept_reports();
+    for old_report in reports
+        .iter()
+        .take(reports.len().saturating_sub(KEPT_REPORTS))
+    {
+        let _ = fs::remove_file(old_report);
+    }
+    Ok(())
+}
+
+// the format of a prefix like html:out.html, or else of the extension of the file.
+fn format_and_path(path: &PathBuf) -> (&'static str, PathBuf) {
+    let value = path.to_string_lossy();
+    for format in ["json", "html", "markdown"] {
+        if let Some(file) = value.strip_prefix(&format!("{}:", format)) {
+            return (format, PathBuf::from(file));
+        }
+    }
+    let format = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => "json",
+        Some("html") => "html",
+        _ => "markdown",
+    };
+    (format, path.clone())
+}
+
+// JSON, a standalone web page or Markdown, see format_and_path. returns the written file.
+pub fn write(
+    path: &PathBuf,
+    environment: &Environment,
+    synced_repos: &[SyncedRepo],
+    failed_repos: &[FailedRepo],
+) -> Result<PathBuf, String> {
+    let (format, path) = format_and_path(path);
+    let mut repos: Vec<ReportedRepo> = synced_repos
+        .iter()
+        .map(|synced_repo| {
+            let failure = failed_repos
+                .iter()
+                .find(|failed_repo| failed_repo.name == synced_repo.name);
+            ReportedRepo {
+                name: &synced_repo.name,
+                state: &synced_repo.state,
+                operation: failure.map(|failed_repo| failed_repo.operation),
+                output: failure.map(|failed_repo| failed_repo.output),
+                duration_ms: synced_repo.duration_ms,
+            }
+        })
+        .collect();
+    repos.sort_by(|a, b| a.name.cmp(b.name));
+    let report = Report { environment, repos };
+    let content = match format {
+        "json" => serde_json::to_string_pretty(&report).map_err(|e| e.to_string())? + "\n",
+        "html" => render_html(&report),
+        _ => render_markdown(&report),
+    };
+    fs::write(&path, content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    keep(&report).map_err(|e| format!("failed to keep a copy: {}", e))?;
+    Ok(path)
+}
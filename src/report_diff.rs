@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use serde::Deserialize;
+
+use crate::report;
+use crate::style;
+
+// the part of a JSON report that is compared, the environment is left out.
+#[derive(Debug, Deserialize)]
+struct SavedReport {
+    repos: Vec<SavedRepo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedRepo {
+    name: String,
+    state: String,
+    // only set for failed repos.
+    #[serde(default)]
+    operation: Option<String>,
+}
+
+fn load(path: &PathBuf) -> Result<BTreeMap<String, SavedRepo>, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let report: SavedReport = serde_json::from_str(&json).map_err(|e| {
+        format!(
+            "{}: {}, only JSON reports can be compared, e.g. from --report sync-report.json",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(report
+        .repos
+        .into_iter()
+        .map(|repo| (repo.name.clone(), repo))
+        .collect())
+}
+
+// the older and the newer report. with last the newer one is compared with the newest kept
+// report that differs from it, so a report that was just written is not compared with itself.
+pub fn resolve(
+    reports: &[PathBuf],
+    against: Option<&String>,
+) -> Result<(PathBuf, PathBuf), String> {
+    match (reports, against) {
+        ([old, new], None) => Ok((old.clone(), new.clone())),
+        ([new], Some(_)) => {
+            let content = fs::read(new).map_err(|e| format!("{}: {}", new.display(), e))?;
+            report::kept_reports()
+                .into_iter()
+                .rev()
+                .find(|kept| fs::read(kept).is_ok_and(|kept_content| kept_content != content))
+                .map(|old| (old, new.clone()))
+                .ok_or("no earlier report kept, run a sync with --report first".to_string())
+        }
+        ([], Some(_)) => match report::kept_reports().as_slice() {
+            [.., old, new] => Ok((old.clone(), new.clone())),
+            _ => Err("less than two reports kept, run syncs with --report first".to_string()),
+        },
+        _ => Err("compare two reports, or one report or none with --against last".to_string()),
+    }
+}
+
+fn print_section(title: &str, color: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    println!("{}:", style::paint(title, color));
+    for line in lines {
+        println!("  {}", line);
+    }
+}
+
+// true when repos fail that didn't fail before.
+pub fn diff(old_path: &PathBuf, new_path: &PathBuf) -> Result<bool, String> {
+    let old = load(old_path)?;
+    let new = load(new_path)?;
+    let mut newly_failing = Vec::new();
+    let mut recovered = Vec::new();
+    let mut appeared = Vec::new();
+    for (name, repo) in &new {
+        let failed_before = old.get(name).map(|old_repo| old_repo.operation.is_some());
+        match (failed_before, &repo.operation) {
+            (Some(false) | None, Some(operation)) => {
+                newly_failing.push(format!("{} ({} failed)", name, operation))
+            }
+            (Some(true), None) => recovered.push(format!("{} ({})", name, repo.state)),
+            _ => {}
+        }
+        if failed_before.is_none() {
+            appeared.push(format!("{} ({})", name, repo.state));
+        }
+    }
+    let removed: Vec<String> = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .cloned()
+        .collect();
+    println!("{} -> {}", old_path.display(), new_path.display());
+    print_section("newly failing", style::ERROR, &newly_failing);
+    print_section("recovered", style::OK, &recovered);
+    print_section("new repos", style::CLONED, &appeared);
+    print_section("removed repos", style::ARCHIVED, &removed);
+    if newly_failing.is_empty() && recovered.is_empty() && appeared.is_empty() && removed.is_empty()
+    {
+        println!("no changes");
+    }
+    Ok(!newly_failing.is_empty())
+}
+
+// report diff exits with 1 when repos started failing.
+pub fn run(report_args: &ArgMatches) {
+    let Some(("diff", diff_args)) = report_args.subcommand() else {
+        return;
+    };
+    let reports: Vec<PathBuf> = diff_args
+        .get_many::<PathBuf>("reports")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    match resolve(&reports, diff_args.get_one::<String>("against"))
+        .and_then(|(old, new)| diff(&old, &new))
+    {
+        Ok(false) => (),
+        Ok(true) => std::process::exit(1),
+        Err(e) => {
+            println!(
+                "{}: {}",
+                style::paint("report diff failed", style::ERROR),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::config::ClassificationRule;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    Credential,
+    Auth,
+    Corrupt,
+    // the repo is owned by another user and not listed in safe.directory.
+    UnsafeDirectory,
+    UpToDate,
+    // a plain error, none of the special cases.
+    Error,
+}
+
+static RULES: OnceLock<Vec<(Regex, Category)>> = OnceLock::new();
+
+fn parse_category(state: &str) -> Result<Category, String> {
+    match state {
+        "credential" => Ok(Category::Credential),
+        "auth" => Ok(Category::Auth),
+        "corrupt" => Ok(Category::Corrupt),
+        "unsafe_directory" => Ok(Category::UnsafeDirectory),
+        "up_to_date" => Ok(Category::UpToDate),
+        "error" => Ok(Category::Error),
+        _ => Err(format!(
+            "unknown state '{}', use one of credential, auth, corrupt, unsafe_directory, up_to_date, error",
+            state
+        )),
+    }
+}
+
+pub fn set_rules(rules: &Vec<ClassificationRule>) -> Result<(), String> {
+    let mut compiled = Vec::new();
+    for rule in rules {
+        let regex = Regex::new(&rule.pattern).map_err(|e| e.to_string())?;
+        compiled.push((regex, parse_category(&rule.state)?));
+    }
+    let _ = RULES.set(compiled);
+    Ok(())
+}
+
+// the category of the first user rule matching git's output, None leaves it to the built-in checks.
+pub fn classify(output: &str) -> Option<Category> {
+    RULES
+        .get()?
+        .iter()
+        .find(|(regex, _)| regex.is_match(output))
+        .map(|(_, category)| *category)
+}
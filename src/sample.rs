@@ -0,0 +1,21 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+// restricts repos to the first `limit` by name or `sample` random ones, for trying flags on a few repos.
+pub fn restrict<T>(
+    mut repos: Vec<T>,
+    limit: Option<usize>,
+    sample: Option<usize>,
+    name: impl Fn(&T) -> &str,
+) -> Vec<T> {
+    if let Some(sample) = sample {
+        // RandomState is seeded randomly per process, which is random enough to pick repos.
+        let random_state = RandomState::new();
+        repos.sort_by_cached_key(|repo| random_state.hash_one(name(repo)));
+        repos.truncate(sample);
+    } else if let Some(limit) = limit {
+        repos.sort_by(|a, b| name(a).cmp(name(b)));
+        repos.truncate(limit);
+    }
+    repos
+}
@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use reqwest::Url;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::git;
+use crate::state;
+use crate::style;
+
+// creates a team repo from a template, adds it to the team so later syncs pick it up
+// and clones it like a sync would.
+async fn create(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    protocol: &str,
+    new_args: &ArgMatches,
+) -> Result<(), String> {
+    let name = new_args.get_one::<String>("name").unwrap();
+    let template = new_args.get_one::<String>("template").unwrap();
+    let Some((template_owner, _)) = template.split_once('/') else {
+        return Err(format!("{} is not of the form owner/repo", template));
+    };
+    let owner = new_args
+        .get_one::<String>("owner")
+        .map_or(template_owner, |owner| owner.as_str());
+    let name = if name.starts_with(github_team_prefix.as_str()) {
+        name.clone()
+    } else {
+        format!("{}{}", github_team_prefix, name)
+    };
+
+    let remote_repo = api::create_from_template(
+        api_client,
+        github_team_repo_url,
+        template,
+        owner,
+        &name,
+        !new_args.get_flag("public"),
+    )
+    .await?;
+    println!(
+        "{}: created from {}",
+        style::paint(&remote_repo.name, style::WARNING),
+        template
+    );
+    if let Err(e) =
+        api::add_to_team(api_client, github_team_repo_url, owner, &remote_repo.name).await
+    {
+        println!(
+            "{}: not added to the team, it won't be synced: {}",
+            style::paint(&remote_repo.name, style::ERROR),
+            e
+        );
+    }
+
+    let dir_name =
+        git::local_dir_name(&remote_repo, github_team_prefix, &state::load().directories);
+    let output = git::git_clone(
+        &remote_repo,
+        repo_root_dir.clone(),
+        dir_name.clone(),
+        &[],
+        protocol,
+        |_| {},
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "failed to clone: {}",
+            String::from_utf8_lossy(output.stderr.trim_ascii())
+        ));
+    }
+    println!(
+        "{}: cloned to {}",
+        style::paint(&remote_repo.name, style::WARNING),
+        dir_name
+    );
+    Ok(())
+}
+
+pub async fn run(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+    protocol: &str,
+    new_args: &ArgMatches,
+) {
+    if let Err(e) = create(
+        api_client,
+        github_team_repo_url,
+        github_team_prefix,
+        repo_root_dir,
+        protocol,
+        new_args,
+    )
+    .await
+    {
+        println!(
+            "{}: {}",
+            style::paint("failed to create repo", style::ERROR),
+            e
+        );
+        std::process::exit(1);
+    }
+}
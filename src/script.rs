@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::git::RemoteRepo;
+
+// what the classify(repo) function of a script decided, nil keeps the defaults.
+#[derive(Debug, Default, Deserialize)]
+pub struct Classification {
+    #[serde(default)]
+    pub skip: bool,
+    pub dir: Option<String>,
+    #[serde(default)]
+    pub clone_args: Vec<String>,
+}
+
+#[cfg(feature = "lua")]
+pub struct Script {
+    lua: mlua::Lua,
+}
+
+#[cfg(feature = "lua")]
+impl Script {
+    pub fn load(path: &PathBuf) -> Result<Script, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let lua = mlua::Lua::new();
+        lua.load(&source)
+            .set_name(path.display().to_string())
+            .exec()
+            .map_err(|e| e.to_string())?;
+        Ok(Script { lua })
+    }
+
+    pub fn classify(&self, remote_repo: &RemoteRepo) -> Result<Classification, String> {
+        use mlua::LuaSerdeExt;
+        let classify = self
+            .lua
+            .globals()
+            .get::<mlua::Function>("classify")
+            .map_err(|e| e.to_string())?;
+        let repo = self.lua.to_value(remote_repo).map_err(|e| e.to_string())?;
+        let result = classify
+            .call::<mlua::Value>(repo)
+            .map_err(|e| e.to_string())?;
+        let classification: Option<Classification> =
+            self.lua.from_value(result).map_err(|e| e.to_string())?;
+        Ok(classification.unwrap_or_default())
+    }
+}
+
+#[cfg(not(feature = "lua"))]
+pub struct Script;
+
+#[cfg(not(feature = "lua"))]
+impl Script {
+    pub fn load(_path: &PathBuf) -> Result<Script, String> {
+        Err(
+            "reposync was built without the lua feature, e.g. cargo install --features lua"
+                .to_string(),
+        )
+    }
+
+    pub fn classify(&self, _remote_repo: &RemoteRepo) -> Result<Classification, String> {
+        Ok(Classification::default())
+    }
+}
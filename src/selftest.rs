@@ -0,0 +1,313 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use clap::ArgMatches;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+
+use crate::git;
+use crate::style;
+
+const PREFIX: &str = "selftest-";
+const TEAM_REPOS_PATH: &str = "/orgs/selftest/teams/selftest/repos";
+
+// git with a fixed identity and without signing, so commits work with any user config.
+fn git_output(dir: &PathBuf, args: &[&str]) -> Result<String, String> {
+    let output = git::git_command()
+        .arg("-c")
+        .arg("user.name=reposync selftest")
+        .arg("-c")
+        .arg("user.email=selftest@reposync.invalid")
+        .arg("-c")
+        .arg("commit.gpgsign=false")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(output.stderr.trim_ascii())
+        ));
+    }
+    Ok(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+// a bare repo standing in for a team repo on GitHub, with a work tree to push commits from.
+pub struct FakeRemote {
+    pub name: String,
+    bare: PathBuf,
+    work: PathBuf,
+}
+
+impl FakeRemote {
+    pub fn create(dir: &PathBuf, name: &str) -> Result<FakeRemote, String> {
+        let remote = FakeRemote {
+            name: name.to_string(),
+            bare: dir.join("remotes").join(format!("{}.git", name)),
+            work: dir.join("work").join(name),
+        };
+        fs::create_dir_all(&remote.bare).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&remote.work).map_err(|e| e.to_string())?;
+        git_output(&remote.bare, &["init", "--bare", "--quiet"])?;
+        git_output(&remote.bare, &["symbolic-ref", "HEAD", "refs/heads/main"])?;
+        git_output(&remote.work, &["init", "--quiet"])?;
+        git_output(&remote.work, &["remote", "add", "origin", &remote.url()])?;
+        remote.commit("initial commit")?;
+        Ok(remote)
+    }
+
+    pub fn url(&self) -> String {
+        self.bare.to_string_lossy().to_string()
+    }
+
+    // pushes a commit to main and returns it.
+    pub fn commit(&self, message: &str) -> Result<String, String> {
+        let readme = self.work.join("README.md");
+        let content = fs::read_to_string(&readme).unwrap_or_default();
+        fs::write(&readme, format!("{}{}\n", content, message)).map_err(|e| e.to_string())?;
+        git_output(&self.work, &["add", "README.md"])?;
+        git_output(&self.work, &["commit", "--quiet", "-m", message])?;
+        git_output(
+            &self.work,
+            &["push", "--quiet", "origin", "HEAD:refs/heads/main"],
+        )?;
+        git_output(&self.work, &["rev-parse", "HEAD"])
+    }
+
+    // the repo as the team repo list of the GitHub API describes it.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "archived": false,
+            "clone_url": self.url(),
+            "ssh_url": self.url(),
+        })
+    }
+}
+
+// answers the team repo list like the GitHub API, every other endpoint is not found.
+pub struct StubApi {
+    pub address: SocketAddr,
+    pub repos: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+impl StubApi {
+    pub async fn start() -> Result<StubApi, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| e.to_string())?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        let repos: Arc<Mutex<Vec<serde_json::Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let served = repos.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(respond(stream, served.clone()));
+            }
+        });
+        Ok(StubApi { address, repos })
+    }
+
+    pub fn team_repo_url(&self) -> String {
+        format!("http://{}{}", self.address, TEAM_REPOS_PATH)
+    }
+}
+
+async fn respond(mut stream: TcpStream, repos: Arc<Mutex<Vec<serde_json::Value>>>) {
+    let mut buffer: Vec<u8> = Vec::new();
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        let mut chunk = [0; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(read) if read > 0 && buffer.len() < 64 * 1024 => {
+                buffer.extend_from_slice(&chunk[..read])
+            }
+            _ => return,
+        }
+    }
+    let head = String::from_utf8_lossy(&buffer).to_string();
+    let target = head.split_whitespace().nth(1).unwrap_or("");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (status, body) = if path == TEAM_REPOS_PATH {
+        // everything fits on the first page, later pages are empty like on GitHub.
+        let page = query
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("page="))
+            .unwrap_or("1");
+        let repos = if page == "1" {
+            serde_json::Value::Array(repos.lock().unwrap().clone())
+        } else {
+            serde_json::json!([])
+        };
+        ("200 OK", repos.to_string())
+    } else {
+        ("404 Not Found", r#"{"message":"Not Found"}"#.to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+// a regular sync of this binary against the stub API. state and config live in the
+// selftest dir and settings from the environment are left out, so only git and the
+// binary itself are tested, not the configuration of this machine.
+async fn sync(dir: &PathBuf, api: &StubApi) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut command = Command::new(exe);
+    for (key, _) in std::env::vars_os() {
+        if key.to_string_lossy().starts_with("REPOSYNC_") {
+            command.env_remove(key);
+        }
+    }
+    command
+        .env("XDG_STATE_HOME", dir.join("state"))
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("LOCALAPPDATA", dir.join("state"))
+        .env("APPDATA", dir.join("config"))
+        .arg("--github_team_repo_url")
+        .arg(api.team_repo_url())
+        .arg("--repo_root_dir")
+        .arg(dir.join("root"))
+        .arg("--github_token")
+        .arg("selftest")
+        .arg("--github_team_prefix")
+        .arg(PREFIX)
+        .arg("--protocol")
+        .arg("https")
+        .arg("--token-credentials")
+        .arg("--ascii")
+        .arg("--fail-fast");
+    let output = tokio::process::Command::from(command)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "sync exited with {}:\n{}",
+            output.status,
+            String::from_utf8_lossy(output.stdout.trim_ascii())
+        ));
+    }
+    Ok(())
+}
+
+fn expect_head(dir: &PathBuf, repo: &str, commit: &str) -> Result<(), String> {
+    let path = dir.join("root").join(repo);
+    if !path.exists() {
+        return Err(format!("{} was not cloned", repo));
+    }
+    let head = git_output(&path, &["rev-parse", "HEAD"])?;
+    if head != commit {
+        return Err(format!("{} is at {} instead of {}", repo, head, commit));
+    }
+    Ok(())
+}
+
+fn report(step: &str, result: Result<(), String>) -> bool {
+    match result {
+        Ok(()) => {
+            println!("{}: ok", style::paint(step, style::OK));
+            true
+        }
+        Err(e) => {
+            println!("{}: {}", style::paint(step, style::ERROR), e);
+            false
+        }
+    }
+}
+
+async fn scenario(dir: &PathBuf) -> bool {
+    if let Err(e) = fs::create_dir_all(dir.join("root")) {
+        return report("create repo root dir", Err(e.to_string()));
+    }
+    let created = ["alpha", "beta", "gamma", "unrelated"]
+        .iter()
+        .map(|name| match *name {
+            "unrelated" => FakeRemote::create(dir, name),
+            name => FakeRemote::create(dir, &format!("{}{}", PREFIX, name)),
+        })
+        .collect::<Result<Vec<FakeRemote>, String>>();
+    let remotes = match created {
+        Ok(remotes) => remotes,
+        Err(e) => return report("create fake remotes", Err(e)),
+    };
+    let api = match StubApi::start().await {
+        Ok(api) => api,
+        Err(e) => return report("start stub API", Err(e)),
+    };
+    let [alpha, beta, gamma, unrelated] = &remotes[..] else {
+        return false;
+    };
+    let alpha_head = git_output(&alpha.work, &["rev-parse", "HEAD"]).unwrap_or_default();
+    let beta_head = git_output(&beta.work, &["rev-parse", "HEAD"]).unwrap_or_default();
+    api.repos
+        .lock()
+        .unwrap()
+        .extend([alpha.json(), beta.json(), unrelated.json()]);
+
+    let mut passed = report("first sync", sync(dir, &api).await);
+    passed &= report(
+        "clones team repos",
+        expect_head(dir, "alpha", &alpha_head).and(expect_head(dir, "beta", &beta_head)),
+    );
+    passed &= report(
+        "leaves out repos without the team prefix",
+        if dir.join("root").join("unrelated").exists() {
+            Err("unrelated was cloned".to_string())
+        } else {
+            Ok(())
+        },
+    );
+
+    let gamma_head = git_output(&gamma.work, &["rev-parse", "HEAD"]).unwrap_or_default();
+    api.repos.lock().unwrap().push(gamma.json());
+    let new_head = match alpha.commit("second commit") {
+        Ok(new_head) => new_head,
+        Err(e) => return report("push to fake remote", Err(e)),
+    };
+    passed &= report("second sync", sync(dir, &api).await);
+    passed &= report("pulls new commits", expect_head(dir, "alpha", &new_head));
+    passed &= report(
+        "keeps up to date repos",
+        expect_head(dir, "beta", &beta_head),
+    );
+    passed &= report(
+        "clones new team repos",
+        expect_head(dir, "gamma", &gamma_head),
+    );
+    passed
+}
+
+// syncs fake team repos served from bare repos on disk and checks the result,
+// returns whether every check passed.
+pub async fn run(selftest_args: &ArgMatches) -> bool {
+    let keep = selftest_args.get_flag("keep");
+    let dir = std::env::temp_dir().join(format!("reposync-selftest-{}", std::process::id()));
+    // left over by an interrupted run with the same pid.
+    let _ = fs::remove_dir_all(&dir);
+    let passed = scenario(&dir).await;
+    if keep {
+        println!("kept {}", dir.display());
+    } else if let Err(e) = fs::remove_dir_all(&dir) {
+        println!(
+            "{}: {}",
+            style::paint(
+                &format!("failed to remove {}", dir.display()),
+                style::WARNING
+            ),
+            e
+        );
+    }
+    passed
+}
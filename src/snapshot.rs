@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::git;
+use crate::git::list_local_repos;
+use crate::paths;
+use crate::state;
+use crate::style;
+
+// HEAD commits of all repos in the repo root dir at one point in time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub created_at: String,
+    // directory name to commit.
+    pub repos: BTreeMap<String, String>,
+}
+
+fn snapshot_dir() -> Result<PathBuf, String> {
+    paths::state_dir()
+        .map(|dir| dir.join("snapshots"))
+        .ok_or("no state directory, neither HOME nor XDG_STATE_HOME is set".to_string())
+}
+
+fn snapshot_file(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("{} is not a valid snapshot name", name));
+    }
+    Ok(snapshot_dir()?.join(format!("{}.json", name)))
+}
+
+async fn save(repo_root_dir: &PathBuf, name: &str) -> Result<usize, String> {
+    let path = snapshot_file(name)?;
+    let mut repos = BTreeMap::new();
+    for local_repo in list_local_repos(repo_root_dir) {
+        match git::git_head(&local_repo.path).await {
+            Some(commit) => {
+                repos.insert(local_repo.name, commit);
+            }
+            None => println!(
+                "{}: no HEAD commit, left out",
+                style::paint(&local_repo.name, style::WARNING)
+            ),
+        }
+    }
+    let count = repos.len();
+    let snapshot = Snapshot {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        repos,
+    };
+    fs::create_dir_all(snapshot_dir()?).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    fs::write(&path, json + "\n").map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+// repos with local changes are skipped instead of risking them on checkout.
+fn restore(repo_root_dir: &PathBuf, name: &str, branch: Option<&String>) -> Result<(), String> {
+    let path = snapshot_file(name)?;
+    let json = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let snapshot: Snapshot =
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let sync_state = state::load();
+    let mut failed = 0;
+    for (dir, commit) in &snapshot.repos {
+        // clones moved by a rename since the snapshot are found under their new name.
+        let dir = &sync_state.current_name(dir);
+        let repo_path = repo_root_dir.join(dir);
+        if !repo_path.exists() {
+            failed += 1;
+            println!("{}: missing", style::paint(dir, style::ERROR));
+            continue;
+        }
+        if git::dirty_files(&repo_path).is_none_or(|files| !files.is_empty()) {
+            failed += 1;
+            println!(
+                "{}: local changes, skipped",
+                style::paint(dir, style::ERROR)
+            );
+            continue;
+        }
+        match git::checkout(&repo_path, commit, branch) {
+            Ok(output) if output.status.success() => {
+                println!("{}: {}", style::paint(dir, style::OK), commit)
+            }
+            Ok(output) => {
+                failed += 1;
+                println!(
+                    "{}: {}",
+                    style::paint(dir, style::ERROR),
+                    String::from_utf8_lossy(output.stderr.trim_ascii())
+                )
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", style::paint(dir, style::ERROR), e)
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} repos were not restored", failed));
+    }
+    Ok(())
+}
+
+fn list() -> Result<(), String> {
+    let mut names: Vec<String> = match fs::read_dir(snapshot_dir()?) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .strip_suffix(".json")
+                    .map(|name| name.to_string())
+            })
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+    names.sort();
+    if names.is_empty() {
+        println!("no snapshots");
+    }
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+pub async fn run(repo_root_dir: &PathBuf, snapshot_args: &ArgMatches) {
+    let result = match snapshot_args.subcommand() {
+        Some(("save", save_args)) => {
+            let name = save_args.get_one::<String>("name").unwrap();
+            save(repo_root_dir, name)
+                .await
+                .map(|count| println!("saved {} repos as {}", count, name))
+        }
+        Some(("restore", restore_args)) => restore(
+            repo_root_dir,
+            restore_args.get_one::<String>("name").unwrap(),
+            restore_args.get_one::<String>("branch"),
+        ),
+        _ => list(),
+    };
+    if let Err(e) = result {
+        println!("{}: {}", style::paint("snapshot failed", style::ERROR), e);
+        std::process::exit(1);
+    }
+}
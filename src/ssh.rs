@@ -0,0 +1,181 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::git;
+use crate::style;
+
+pub const GITHUB_HOST: &str = "github.com";
+
+// https://docs.github.com/en/authentication/keeping-your-account-and-data-secure/githubs-ssh-key-fingerprints
+const GITHUB_HOST_KEYS: [&str; 3] = [
+    "github.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl",
+    "github.com ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBEmKSENjQEezOmxkZMy7opKgwFB9nkt5YRrYMjNuG5N87uRgg6CLrbo5wAdT/y6v0mKV0U2w0WZ2YB/++Tpockg=",
+    "github.com ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABgQCj7ndNxQowgcQnjshcLrqPEiiphnt+VTTvDP6mHBL9j1aNUkY4Ue1gvwnGLVlOhGeYrnZaMgRK6+PKCUXaDbC7qtbW8gIkhL7aGCsOr/C56SJMy/BCZfxd1nWzAOxSDPgVsmerOBYfNqltV9/hWCqBywINIR+5dIg6JTJ72pcEpEjcYgXkE2YEFXV1JHnsKgbLWNlhScqb2UmyRkQyytRLtL+38TGxkxCflmO+5Z8CSSNY7GidjMIZ7Q4zMjA2n1nGrlTDkzwDCsw+wqFPGQA179cnfGWOWRVruj16z6XyvxvjJwbz0wQZ75XK5tKSb7FNyeIEs4TT4jk+S4dhPeAUC5y+bDYirYgM4GC7uEnztnZyaVWQ7B381AK4Qdrwt51ZqExKbQpTUNn+EjqoTwvqNj4kqx5QUCI0ThS/YkOxJCXmPUWZbhjpCg56i+2aB6CmK2JGhn57K5mj0MNdBXA4/WnwH6XoPWJzK5Nyu2zB3nAZp+S5hpQs+p1vN1/wsjk=",
+];
+
+pub fn known_hosts_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".ssh").join("known_hosts"))
+}
+
+pub fn is_known_host(host: &str) -> bool {
+    let Some(path) = known_hosts_path() else {
+        return false;
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return false;
+    };
+    for line in content.lines() {
+        let hosts = line.split_whitespace().next().unwrap_or("");
+        if hosts.split(',').any(|entry| entry == host) {
+            return true;
+        }
+    }
+    // hashed entries (HashKnownHosts) can only be resolved by ssh-keygen itself.
+    return Command::new("ssh-keygen")
+        .arg("-F")
+        .arg(host)
+        .arg("-f")
+        .arg(&path)
+        .output()
+        .is_ok_and(|output| output.status.success());
+}
+
+pub fn add_github_host_keys() -> Result<PathBuf, std::io::Error> {
+    let path = known_hosts_path().ok_or(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "HOME is not set",
+    ))?;
+    if let Some(ssh_dir) = path.parent() {
+        fs::create_dir_all(ssh_dir)?;
+    }
+    let needs_newline =
+        fs::read(&path).is_ok_and(|content| content.last().is_some_and(|last| *last != b'\n'));
+    let mut known_hosts = OpenOptions::new().create(true).append(true).open(&path)?;
+    if needs_newline {
+        writeln!(known_hosts)?;
+    }
+    for host_key in GITHUB_HOST_KEYS {
+        writeln!(known_hosts, "{}", host_key)?;
+    }
+    return Ok(path);
+}
+
+// e.g. ssh://git@github.com for git@github.com:org/repo.git, None for other protocols.
+fn destination(url: &str) -> Option<String> {
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        let authority = rest.split('/').next()?;
+        return Some(format!("ssh://{}", authority));
+    }
+    if url.contains("://") {
+        return None;
+    }
+    let (host, _) = url.split_once(':')?;
+    Some(format!("ssh://{}", host))
+}
+
+// the ssh git would run anyway, so keys and options of the user still apply.
+fn base_ssh_command() -> String {
+    if let Ok(command) = std::env::var("GIT_SSH_COMMAND") {
+        return command;
+    }
+    git::git_command()
+        .arg("config")
+        .arg("--get")
+        .arg("core.sshCommand")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+        .unwrap_or("ssh".to_string())
+}
+
+// one ControlMaster per host, the git processes of a sync share its connection instead of
+// each doing a handshake. the masters are closed on drop.
+pub struct Multiplex {
+    dir: PathBuf,
+    destinations: Vec<String>,
+    ssh_command: String,
+}
+
+impl Multiplex {
+    // starts a master for every host of the urls. a host without a master still works,
+    // its git processes fall back to their own connections.
+    pub fn start<'a>(urls: impl Iterator<Item = &'a String>) -> Result<Multiplex, String> {
+        // sockets are limited to about 100 characters, the temp dir of macOS is too long.
+        let dir = PathBuf::from("/tmp").join(format!("reposync-ssh-{}", std::process::id()));
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+                .map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let mut destinations: Vec<String> = urls.filter_map(|url| destination(url)).collect();
+        destinations.sort();
+        destinations.dedup();
+        let base = base_ssh_command();
+        let options = format!(
+            "-o ControlPath={}/%C -o ControlPersist=5m",
+            dir.to_string_lossy()
+        );
+        let mut multiplex = Multiplex {
+            ssh_command: format!("{} {} -o ControlMaster=no", base, options),
+            dir,
+            destinations: Vec::new(),
+        };
+        for destination in destinations {
+            // -f forks once authenticated, the output is not waited for because the
+            // forked master would hold it open.
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(format!(
+                    "{} {} -o ControlMaster=yes -f -N \"$1\"",
+                    base, options
+                ))
+                .arg("sh")
+                .arg(&destination)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            match status {
+                Ok(status) if status.success() => multiplex.destinations.push(destination),
+                _ => println!(
+                    "{}: its repos use their own connections",
+                    style::paint(
+                        &format!("failed to open a shared connection to {}", destination),
+                        style::WARNING
+                    )
+                ),
+            }
+        }
+        Ok(multiplex)
+    }
+
+    // for GIT_SSH_COMMAND, only uses the masters and never becomes one.
+    pub fn ssh_command(&self) -> &str {
+        &self.ssh_command
+    }
+}
+
+impl Drop for Multiplex {
+    fn drop(&mut self) {
+        for destination in &self.destinations {
+            let _ = Command::new("ssh")
+                .arg("-o")
+                .arg(format!("ControlPath={}/%C", self.dir.to_string_lossy()))
+                .arg("-O")
+                .arg("exit")
+                .arg(destination)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
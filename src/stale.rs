@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use clap::ArgMatches;
+use reqwest::Url;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::git::RemoteRepo;
+use crate::provider;
+use crate::provider::Provider;
+use crate::style;
+use crate::time_format;
+
+pub struct StaleRepo<'a> {
+    pub remote_repo: &'a RemoteRepo,
+    pub pushed_at: Option<DateTime<Utc>>,
+}
+
+// active team repos without a push in the window, repos that never saw a push are stale too.
+pub fn find_stale_repos(
+    remote_repos: &Vec<RemoteRepo>,
+    older_than: Duration,
+) -> Vec<StaleRepo<'_>> {
+    let now = Utc::now();
+    let mut stale_repos: Vec<StaleRepo> = remote_repos
+        .iter()
+        .filter(|remote_repo| !remote_repo.archived)
+        .map(|remote_repo| StaleRepo {
+            remote_repo,
+            pushed_at: remote_repo
+                .pushed_at
+                .as_ref()
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                .map(|date| date.with_timezone(&Utc)),
+        })
+        .filter(|stale_repo| match stale_repo.pushed_at {
+            Some(pushed_at) => now
+                .signed_duration_since(pushed_at)
+                .to_std()
+                .is_ok_and(|age| age >= older_than),
+            None => true,
+        })
+        .collect();
+    // oldest first, they are the first candidates for archiving.
+    stale_repos.sort_by_key(|stale_repo| stale_repo.pushed_at);
+    stale_repos
+}
+
+pub async fn print(
+    stale_repos: &Vec<StaleRepo<'_>>,
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    open_prs: bool,
+) {
+    for stale_repo in stale_repos {
+        let pushed = match stale_repo.pushed_at {
+            Some(pushed_at) => format!("last push {}", time_format::timestamp(pushed_at)),
+            None => "never pushed".to_string(),
+        };
+        let pull_requests = if !open_prs {
+            String::new()
+        } else {
+            match stale_repo.remote_repo.full_name() {
+                Some(full_name) => {
+                    match api::open_pull_request_count(api_client, github_team_repo_url, &full_name)
+                        .await
+                    {
+                        Ok(count) => format!(", {} open PRs", count),
+                        Err(e) => format!(", open PRs unknown: {}", e),
+                    }
+                }
+                None => ", open PRs unknown".to_string(),
+            }
+        };
+        println!(
+            "{}: {}{}",
+            style::paint(&stale_repo.remote_repo.name, style::WARNING),
+            pushed,
+            pull_requests
+        );
+    }
+    println!(
+        "{}: {}",
+        style::paint("Stale repos", style::OK),
+        stale_repos.len()
+    );
+}
+
+pub async fn run(
+    provider: &dyn Provider,
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    stale_args: &ArgMatches,
+) {
+    let remote_repos = provider::list_remote_repos(provider).await;
+    let stale_repos = find_stale_repos(
+        &remote_repos,
+        *stale_args.get_one::<Duration>("older_than").unwrap(),
+    );
+    print(
+        &stale_repos,
+        api_client,
+        github_team_repo_url,
+        stale_args.get_flag("open_prs"),
+    )
+    .await;
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::health;
+use crate::paths;
+use crate::read_only;
+
+// decisions that outlive a single run, stored as state.json in the state dir.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    // team repos that are never proposed as new, by repo or directory name.
+    #[serde(default)]
+    pub ignored: BTreeSet<String>,
+    // team repo name to the directory of its clone, recorded once the clone exists so later
+    // runs find it there, also after adoptions, renames and changes to the naming rule.
+    #[serde(default, alias = "adopted")]
+    pub directories: BTreeMap<String, String>,
+    // custom tag to the repo or directory names grouped under it in views.
+    #[serde(default)]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    // former repo or directory name to the new one, kept so older records still resolve.
+    #[serde(default)]
+    pub renamed: BTreeMap<String, String>,
+    // outcome of the last syncs per team repo name, to tell flaky repos from broken ones.
+    #[serde(default)]
+    pub history: BTreeMap<String, Vec<health::Outcome>>,
+    // team repo name to its pushed_at at the last successful sync, --quick skips repos
+    // GitHub reports no push for since.
+    #[serde(default)]
+    pub pushed_at: BTreeMap<String, String>,
+    // directories of clones that are no team repos any more and are left alone, e.g. repos
+    // moved to another team.
+    #[serde(default)]
+    pub unmanaged: BTreeSet<String>,
+    // directories of clones that are no team repos any more but are still pulled.
+    #[serde(default)]
+    pub external: BTreeSet<String>,
+}
+
+impl SyncState {
+    pub fn is_ignored(&self, names: &[&str]) -> bool {
+        names.iter().any(|name| self.ignored.contains(*name))
+    }
+
+    // follows renames to the current name, a rename back to an older name ends the chain.
+    pub fn current_name(&self, name: &str) -> String {
+        let mut current = name.to_string();
+        let mut seen = BTreeSet::new();
+        while let Some(next) = self.renamed.get(&current) {
+            if !seen.insert(current.clone()) {
+                break;
+            }
+            current = next.clone();
+        }
+        current
+    }
+
+    // moves ignores, tags, decisions about former team repos and the history over to the new
+    // repo or directory name.
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if old_name == new_name {
+            return;
+        }
+        if let Some(outcomes) = self.history.remove(old_name) {
+            self.history.insert(new_name.to_string(), outcomes);
+        }
+        for names in [&mut self.ignored, &mut self.unmanaged, &mut self.external] {
+            if names.remove(old_name) {
+                names.insert(new_name.to_string());
+            }
+        }
+        for repos in self.tags.values_mut() {
+            if repos.remove(old_name) {
+                repos.insert(new_name.to_string());
+            }
+        }
+        self.renamed
+            .insert(old_name.to_string(), new_name.to_string());
+    }
+
+    // a no-op with --read-only, decisions of that run are not kept.
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        if read_only::is_read_only() {
+            return Ok(());
+        }
+        let path = state_file().ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no state directory, neither HOME nor XDG_STATE_HOME is set",
+        ))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n")
+    }
+}
+
+pub fn state_file() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("state.json"))
+}
+
+// a missing state file is a fresh start, an unreadable one is reported and ignored.
+pub fn load() -> SyncState {
+    let Some(path) = state_file() else {
+        return SyncState::default();
+    };
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return SyncState::default(),
+        Err(e) => {
+            println!("failed to read {}: {}", path.display(), e);
+            return SyncState::default();
+        }
+    };
+    match serde_json::from_str(&json) {
+        Ok(state) => state,
+        Err(e) => {
+            println!("failed to parse {}: {}", path.display(), e);
+            SyncState::default()
+        }
+    }
+}
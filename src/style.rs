@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use crate::config::Colors;
+
+// what a painted text means, the theme decides the color.
+pub const OK: &str = "ok";
+pub const WARNING: &str = "warning";
+pub const ERROR: &str = "error";
+pub const UPDATED: &str = "updated";
+pub const CLONED: &str = "cloned";
+pub const ARCHIVED: &str = "archived";
+
+// ANSI SGR codes by role.
+const DEFAULT_THEME: [(&str, &str); 6] = [
+    (OK, "32"),
+    (WARNING, "33"),
+    (ERROR, "31"),
+    (UPDATED, "33"),
+    (CLONED, "33"),
+    (ARCHIVED, "33"),
+];
+
+// blue and orange instead of green and red, which look alike with red-green color blindness.
+const COLORBLIND_THEME: [(&str, &str); 6] = [
+    (OK, "34"),
+    (WARNING, "33"),
+    (ERROR, "38;5;208"),
+    (UPDATED, "36"),
+    (CLONED, "34"),
+    (ARCHIVED, "35"),
+];
+
+static THEME: OnceLock<BTreeMap<&'static str, String>> = OnceLock::new();
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+pub fn supports_ansi() -> bool {
+    if is_ascii() {
+        return false;
+    }
+    static SUPPORTS_ANSI: OnceLock<bool> = OnceLock::new();
+    *SUPPORTS_ANSI.get_or_init(|| {
+        if std::env::var_os("NO_COLOR").is_some() || !std::io::stdout().is_terminal() {
+            return false;
+        }
+        if cfg!(windows) {
+            // the legacy conhost prints escape sequences verbatim, modern hosts announce themselves.
+            return std::env::var_os("WT_SESSION").is_some()
+                || std::env::var_os("TERM").is_some()
+                || std::env::var("ConEmuANSI").is_ok_and(|value| value == "ON");
+        }
+        !std::env::var("TERM").is_ok_and(|term| term == "dumb")
+    })
+}
+
+// a named color like red or bright_blue, or SGR codes like 38;5;208.
+fn parse_color(color: &str) -> Result<String, String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    let (name, base) = match color.strip_prefix("bright_") {
+        Some(name) => (name, 90),
+        None => (color, 30),
+    };
+    if let Some(index) = NAMES.iter().position(|known| *known == name) {
+        return Ok((base + index).to_string());
+    }
+    let is_code = !color.is_empty()
+        && color.split(';').all(|code| {
+            !code.is_empty() && code.chars().all(|character| character.is_ascii_digit())
+        });
+    if is_code {
+        return Ok(color.to_string());
+    }
+    Err(format!(
+        "unknown color '{}', use a name like red or bright_blue or ANSI codes like 38;5;208",
+        color
+    ))
+}
+
+// the preset first, then the colors set one by one.
+pub fn set_theme(colors: &Colors) -> Result<(), String> {
+    let preset = match colors.preset.as_deref() {
+        None | Some("default") => DEFAULT_THEME,
+        Some("colorblind") => COLORBLIND_THEME,
+        Some(preset) => {
+            return Err(format!(
+                "unknown preset '{}', use default or colorblind",
+                preset
+            ));
+        }
+    };
+    let mut theme: BTreeMap<&'static str, String> = preset
+        .iter()
+        .map(|(role, code)| (*role, code.to_string()))
+        .collect();
+    let overrides = [
+        (OK, &colors.ok),
+        (WARNING, &colors.warning),
+        (ERROR, &colors.error),
+        (UPDATED, &colors.updated),
+        (CLONED, &colors.cloned),
+        (ARCHIVED, &colors.archived),
+    ];
+    for (role, color) in overrides {
+        if let Some(color) = color {
+            theme.insert(role, parse_color(color)?);
+        }
+    }
+    let _ = THEME.set(theme);
+    Ok(())
+}
+
+fn code(role: &str) -> &str {
+    if let Some(code) = THEME.get().and_then(|theme| theme.get(role)) {
+        return code;
+    }
+    DEFAULT_THEME
+        .iter()
+        .find(|(known, _)| *known == role)
+        .map(|(_, code)| *code)
+        .unwrap_or("0")
+}
+
+pub fn paint(text: &str, role: &str) -> String {
+    if supports_ansi() {
+        format!("\x1b[{}m{}\x1b[0m", code(role), text)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::RemoteRepo;
+use crate::provider;
+use crate::provider::Provider;
+use crate::style;
+
+fn git_output(path: &PathBuf, args: &[&str]) -> Result<String, String> {
+    let output = git::git_command()
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(output.stderr.trim_ascii()).to_string());
+    }
+    Ok(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string())
+}
+
+#[derive(Debug)]
+struct Submodule {
+    path: String,
+    url: String,
+}
+
+// from .gitmodules, so submodules that were never initialized are included.
+fn list_submodules(meta_repo: &PathBuf) -> Result<Vec<Submodule>, String> {
+    let paths = git_output(
+        meta_repo,
+        &[
+            "config",
+            "--file",
+            ".gitmodules",
+            "--get-regexp",
+            r"^submodule\..*\.path$",
+        ],
+    )
+    .map_err(|_| format!("{} has no submodules", meta_repo.display()))?;
+    let mut submodules = Vec::new();
+    for line in paths.lines() {
+        let Some((key, path)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(name) = key
+            .strip_prefix("submodule.")
+            .and_then(|key| key.strip_suffix(".path"))
+        else {
+            continue;
+        };
+        let url = git_output(
+            meta_repo,
+            &[
+                "config",
+                "--file",
+                ".gitmodules",
+                &format!("submodule.{}.url", name),
+            ],
+        )
+        .unwrap_or_default();
+        submodules.push(Submodule {
+            path: path.to_string(),
+            url,
+        });
+    }
+    Ok(submodules)
+}
+
+// relative urls like ../orders-api.git point next to the meta repo, so only the name counts.
+fn find_team_repo<'a>(url: &str, remote_repos: &'a [RemoteRepo]) -> Option<&'a RemoteRepo> {
+    if url.starts_with("../") || url.starts_with("./") {
+        let name = url.trim_end_matches('/').rsplit('/').next()?;
+        let name = name.trim_end_matches(".git");
+        return remote_repos
+            .iter()
+            .find(|remote_repo| remote_repo.name == name);
+    }
+    remote_repos
+        .iter()
+        .find(|remote_repo| git::is_same_remote(url, remote_repo))
+}
+
+fn short(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+// points every submodule of the meta repo that is a team repo at the current HEAD of the
+// default branch of that repo. only the index changes, the commit is left to the user
+// unless commit is set.
+pub fn sync(
+    meta_repo: &PathBuf,
+    remote_repos: &[RemoteRepo],
+    protocol: &str,
+    commit: bool,
+) -> Result<(), String> {
+    if git_output(meta_repo, &["diff", "--cached", "--quiet"]).is_err() {
+        return Err(format!(
+            "{} has staged changes, commit or unstage them first",
+            meta_repo.display()
+        ));
+    }
+    let mut updates: Vec<String> = Vec::new();
+    let mut failed = 0;
+    for submodule in list_submodules(meta_repo)? {
+        let Some(remote_repo) = find_team_repo(&submodule.url, remote_repos) else {
+            println!(
+                "{}: not a team repo, left as is",
+                style::paint(&submodule.path, style::WARNING)
+            );
+            continue;
+        };
+        let head = git_output(
+            meta_repo,
+            &["ls-remote", remote_repo.url(protocol).as_str(), "HEAD"],
+        )
+        .map(|output| output.split_whitespace().next().unwrap_or("").to_string());
+        let head = match head {
+            Ok(head) if !head.is_empty() => head,
+            Ok(_) => {
+                failed += 1;
+                println!(
+                    "{}: {} has no default branch",
+                    style::paint(&submodule.path, style::ERROR),
+                    remote_repo.name
+                );
+                continue;
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", style::paint(&submodule.path, style::ERROR), e);
+                continue;
+            }
+        };
+        let pointer = git_output(
+            meta_repo,
+            &["rev-parse", &format!("HEAD:{}", submodule.path)],
+        )
+        .unwrap_or_default();
+        if pointer == head {
+            println!("{}: up to date", style::paint(&submodule.path, style::OK));
+            continue;
+        }
+        // a gitlink in the index, the submodule itself needs no checkout or fetch.
+        if let Err(e) = git_output(
+            meta_repo,
+            &[
+                "update-index",
+                "--cacheinfo",
+                &format!("160000,{},{}", head, submodule.path),
+            ],
+        ) {
+            failed += 1;
+            println!("{}: {}", style::paint(&submodule.path, style::ERROR), e);
+            continue;
+        }
+        println!(
+            "{}: {} -> {}",
+            style::paint(&submodule.path, style::UPDATED),
+            short(&pointer),
+            short(&head)
+        );
+        updates.push(format!(
+            "{} {}..{}",
+            submodule.path,
+            short(&pointer),
+            short(&head)
+        ));
+    }
+    if !updates.is_empty() {
+        let message = format!(
+            "Update submodules to the default branches\n\n{}\n",
+            updates.join("\n")
+        );
+        if commit {
+            git_output(meta_repo, &["commit", "--quiet", "-m", &message])?;
+            println!("committed {} submodule updates", updates.len());
+        } else {
+            println!(
+                "\nstaged {} submodule updates, commit them with:\ngit -C {} commit -m '{}'",
+                updates.len(),
+                meta_repo.display(),
+                message.trim().replace('\'', "'\\''")
+            );
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} submodules failed", failed));
+    }
+    Ok(())
+}
+
+// the meta repo is a path or the name of a clone in the repo root dir.
+pub async fn run(
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    protocol: &str,
+    submodules_args: &ArgMatches,
+) {
+    let Some(("sync", sync_args)) = submodules_args.subcommand() else {
+        unreachable!()
+    };
+    let meta_repo = sync_args.get_one::<PathBuf>("meta_repo").unwrap();
+    let meta_repo = if meta_repo.is_dir() {
+        meta_repo.clone()
+    } else {
+        repo_root_dir.join(meta_repo)
+    };
+    let remote_repos = provider::list_remote_repos(provider).await;
+    if let Err(e) = sync(
+        &meta_repo,
+        &remote_repos,
+        protocol,
+        sync_args.get_flag("commit"),
+    ) {
+        println!(
+            "{}: {}",
+            style::paint(
+                &format!("failed to sync submodules of {}", meta_repo.display()),
+                style::ERROR
+            ),
+            e
+        );
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,1938 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use clap::ArgMatches;
+use indicatif::MultiProgress;
+use indicatif::ProgressBar;
+use indicatif::ProgressDrawTarget;
+use indicatif::ProgressStyle;
+use reqwest::Url;
+
+use crate::adopt;
+use crate::api::ApiClient;
+use crate::archive;
+use crate::cancel::{AUTH_FAILURE_THRESHOLD, AuthFailureDetector, CancellationToken};
+use crate::children;
+use crate::clipboard;
+use crate::config;
+use crate::disk_space;
+use crate::duplicates;
+use crate::email;
+use crate::events;
+use crate::failure_log;
+use crate::failure_log::FailedRepo;
+use crate::git;
+use crate::git::{LocalRepo, RemoteRepo, list_local_repos};
+use crate::git_version;
+use crate::github_actions;
+use crate::heal;
+use crate::health;
+use crate::last_run;
+use crate::last_run::LastRun;
+use crate::plugin;
+use crate::progress;
+use crate::prompt;
+use crate::provider;
+use crate::provider::Provider;
+use crate::queue;
+use crate::read_only;
+use crate::repo_lock;
+use crate::report;
+use crate::sample;
+use crate::script;
+use crate::ssh;
+use crate::state;
+use crate::style;
+use crate::time_format;
+use crate::transferred;
+use crate::views;
+
+fn is_selected(selected_repos: &Vec<String>, names: &[&str]) -> bool {
+    selected_repos.is_empty()
+        || names
+            .iter()
+            .any(|name| selected_repos.iter().any(|selected| selected == name))
+}
+
+fn set_phase(progress_bar: &ProgressBar, phase: &'static str) {
+    // hidden bars don't render their prefix, so phases are printed as plain lines instead.
+    if progress::mode() == progress::Mode::Lines {
+        println!("{}", phase);
+    }
+    progress_bar.set_prefix(phase);
+}
+
+// a phase bar that also lists the repos currently being processed, tagged with their operation.
+#[derive(Clone)]
+struct PhaseProgress {
+    bar: ProgressBar,
+    in_flight: Arc<Mutex<Vec<String>>>,
+}
+
+impl PhaseProgress {
+    fn new(bar: ProgressBar) -> PhaseProgress {
+        PhaseProgress {
+            bar,
+            in_flight: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn start(&self, operation: &str, name: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        in_flight.push(format!("{} {}", operation, name));
+        self.bar.set_message(in_flight.join(", "));
+    }
+
+    fn finish(&self, operation: &str, name: &str) {
+        let tagged_name = format!("{} {}", operation, name);
+        let mut in_flight = self.in_flight.lock().unwrap();
+        in_flight.retain(|in_flight_name| *in_flight_name != tagged_name);
+        self.bar.set_message(in_flight.join(", "));
+        self.bar.inc(1);
+    }
+}
+
+// shared by all clone and pull tasks of a run.
+#[derive(Clone)]
+struct SyncContext {
+    cancellation: CancellationToken,
+    auth_failures: Arc<AuthFailureDetector>,
+    fail_fast: bool,
+    show_diffstat: bool,
+    pull_options: git::PullOptions,
+    verify_signatures: bool,
+    allowed_signers: Option<PathBuf>,
+    verify_clones: bool,
+    verify_pulls: bool,
+    heal: bool,
+    protocol: String,
+    clone_timeout: Option<Duration>,
+    record_events: bool,
+    // bytes received per repo, only for repos whose fetch transferred objects.
+    downloaded: Arc<Mutex<BTreeMap<String, u64>>>,
+    // cloned with --shallow-since, so a shallow clone is no warning.
+    shallow_clones: bool,
+    // pulls only repos whose remote HEAD moved, see handle_new_pull.
+    quick: bool,
+    // branch by directory for repos that are kept on another branch than the default one.
+    pinned_branches: Arc<BTreeMap<String, String>>,
+    // directories of repos that are kept at their latest release tag.
+    release_repos: Arc<BTreeSet<String>>,
+    // from --deadline, nothing starts that is expected to finish after it.
+    deadline: Option<Instant>,
+}
+
+impl SyncContext {
+    fn record_download(&self, name: &str, bytes: u64) {
+        self.downloaded
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), bytes);
+    }
+
+    // repos without recorded durations are only deferred once the deadline has passed.
+    fn past_deadline(&self, expected_duration: Option<Duration>) -> bool {
+        self.deadline.is_some_and(|deadline| {
+            Instant::now() + expected_duration.unwrap_or_default() >= deadline
+        })
+    }
+
+    fn record_failure(&self, is_auth_error: bool, message: &str) {
+        self.auth_failures.record_failure(is_auth_error, message);
+        if self.fail_fast {
+            self.cancellation.cancel();
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    CloneError,
+    PullError,
+    Updated,
+    Cloned,
+    PullNoOp,
+    Skipped,
+    LocalCommitsOnDefault,
+    UnverifiedSignature,
+    CorruptRepo,
+    Healed,
+    CredentialError,
+    // owned by another user and refused by git's safe.directory check.
+    UnsafeDirectory,
+    // not on the branch pinned in the config, or with local commits on it.
+    PinDiverged,
+    // not started because it would have run past --deadline.
+    Deferred,
+    // gone from the team list but still on GitHub, e.g. moved to another team.
+    TransferredOut,
+    // a warning that left the clone behind its remote, git would overwrite local changes.
+    NotPulled,
+    // not an error, but worth a look, e.g. a dirty or shallow clone.
+    Warning,
+}
+
+// names listed per bucket in compact mode before the rest is only counted.
+const COMPACT_NAME_LIMIT: usize = 10;
+
+// one line like 212 up to date; 3 updated: a, b, c. empty buckets are left out.
+fn compact_summary(buckets: &[(&str, &Vec<GitResponse>, bool)]) -> String {
+    let parts: Vec<String> = buckets
+        .iter()
+        .filter(|(_, repos, _)| !repos.is_empty())
+        .map(|(label, repos, list_names)| {
+            let count = format!("{} {}", repos.len(), label);
+            if !list_names {
+                return count;
+            }
+            let mut names: Vec<&str> = repos
+                .iter()
+                .take(COMPACT_NAME_LIMIT)
+                .map(|repo| repo.name.as_str())
+                .collect();
+            let more = repos.len().saturating_sub(COMPACT_NAME_LIMIT);
+            let more = format!("{} more", more);
+            if repos.len() > COMPACT_NAME_LIMIT {
+                names.push(&more);
+            }
+            format!("{}: {}", count, names.join(", "))
+        })
+        .collect();
+    if parts.is_empty() {
+        return "nothing synced".to_string();
+    }
+    parts.join("; ")
+}
+
+impl State {
+    // for the progress lines, the summary explains each state in detail.
+    fn label(&self) -> &'static str {
+        match self {
+            State::CloneError | State::PullError => "failed, details follow in the summary",
+            State::Updated => "updated",
+            State::Cloned => "cloned",
+            State::PullNoOp => "up to date",
+            State::Skipped => "skipped",
+            State::Deferred => "deferred, the deadline was reached",
+            State::LocalCommitsOnDefault => "local commits on the default branch",
+            State::UnverifiedSignature => "HEAD not signed by a trusted key",
+            State::CorruptRepo => "corrupt",
+            State::Healed => "healed",
+            State::CredentialError => "no usable https credentials",
+            State::UnsafeDirectory => "owned by another user, refused by git",
+            State::PinDiverged => "diverged from its pinned branch",
+            State::TransferredOut => "moved out of the team",
+            State::NotPulled | State::Warning => "warning, details follow in the summary",
+        }
+    }
+}
+
+const CORRUPT_REPO_HINT: &str =
+    "move the directory out of the repo root dir and run reposync again to get a fresh clone.";
+
+// healing moves and re-clones whole directories, so it stays on the blocking pool.
+// a repo that could not be healed counts as failed, also for --fail-fast.
+async fn heal_repo(local_repo: &LocalRepo, reason: String, context: &SyncContext) -> GitResponse {
+    let repo_to_heal = local_repo.clone();
+    match tokio::task::spawn_blocking(move || heal::heal(&repo_to_heal))
+        .await
+        .unwrap()
+    {
+        Ok(message) => GitResponse {
+            name: local_repo.name.clone(),
+            message: format!("{}\n{}", reason, message),
+            state: State::Healed,
+        },
+        Err(heal_message) => {
+            let message = format!("{}\nheal failed: {}", reason, heal_message);
+            context.record_failure(false, &message);
+            GitResponse {
+                name: local_repo.name.clone(),
+                message,
+                state: State::PullError,
+            }
+        }
+    }
+}
+
+// a corrupt repo counts as failed, also for --fail-fast.
+fn corrupt_repo(name: String, fsck_message: String, context: &SyncContext) -> GitResponse {
+    context.record_failure(false, &fsck_message);
+    GitResponse {
+        name,
+        message: format!("{}\nhint: {}", fsck_message, CORRUPT_REPO_HINT),
+        state: State::CorruptRepo,
+    }
+}
+
+#[derive(Debug)]
+struct GitResponse {
+    name: String,
+    message: String,
+    state: State,
+}
+
+fn skipped(name: String, progress: &PhaseProgress) -> GitResponse {
+    progress.bar.inc(1);
+    GitResponse {
+        name,
+        message: "".into(),
+        state: State::Skipped,
+    }
+}
+
+fn deferred(name: String, progress: &PhaseProgress) -> GitResponse {
+    progress.bar.inc(1);
+    GitResponse {
+        name,
+        message: "".into(),
+        state: State::Deferred,
+    }
+}
+
+// with --quick a repo without a push since its last sync is not even asked, the others
+// are only pulled when the HEAD of origin differs from the local one.
+async fn handle_new_pull(
+    local_repo: LocalRepo,
+    pushed_since_last_sync: bool,
+    expected_duration: Option<Duration>,
+    priority: bool,
+    progress: PhaseProgress,
+    context: SyncContext,
+) -> GitResponse {
+    if context.cancellation.is_cancelled() {
+        return skipped(local_repo.name, &progress);
+    }
+    if !priority && context.past_deadline(expected_duration) {
+        return deferred(local_repo.name, &progress);
+    }
+    let _lock = repo_lock::lock(&local_repo.path).await;
+    progress.start("pull", &local_repo.name);
+    if context.release_repos.contains(&local_repo.name) {
+        let moved = git::checkout_latest_release(&local_repo.path).await;
+        progress.finish("pull", &local_repo.name);
+        return release_response(local_repo.name, moved, State::Updated, State::PullNoOp);
+    }
+    let pinned_branch = context.pinned_branches.get(&local_repo.name);
+    if let Some(pinned_branch) = pinned_branch
+        && let Err(message) = git::switch_to_pinned_branch(&local_repo.path, pinned_branch).await
+    {
+        progress.finish("pull", &local_repo.name);
+        return GitResponse {
+            name: local_repo.name,
+            message,
+            state: State::PinDiverged,
+        };
+    }
+    // a repo that just switched to its pin needs the pull.
+    if context.quick && pinned_branch.is_none() {
+        let unchanged = !pushed_since_last_sync || {
+            let remote_head = git::git_remote_head(&local_repo.path).await;
+            remote_head.is_some() && remote_head == git::git_head(&local_repo.path).await
+        };
+        if unchanged {
+            progress.finish("pull", &local_repo.name);
+            return GitResponse {
+                name: local_repo.name,
+                message: "".into(),
+                state: State::PullNoOp,
+            };
+        }
+    }
+    let old_head = if context.show_diffstat || context.record_events {
+        git::git_head(&local_repo.path).await
+    } else {
+        None
+    };
+    let mut downloaded: Option<u64> = None;
+    let response = git::git_pull(local_repo.clone(), &context.pull_options, |pull_progress| {
+        downloaded = pull_progress.bytes.or(downloaded)
+    })
+    .await;
+    if let Some(bytes) = downloaded {
+        context.record_download(&local_repo.name, bytes);
+    }
+    progress.finish("pull", &local_repo.name);
+    let _ = match response {
+        Err(message) => {
+            context.record_failure(false, &message.to_string());
+            return GitResponse {
+                name: local_repo.name,
+                message: message.to_string(),
+                state: State::PullError,
+            };
+        }
+        Ok(output) => {
+            let error_message = String::from_utf8_lossy(output.stderr.trim_ascii()).to_string();
+            let info_message = String::from_utf8_lossy(output.stdout.trim_ascii()).to_string();
+
+            // Any non-zero exit from git pull is an error (e.g. unresolved conflicts).
+            if !output.status.success() {
+                if git::is_dirty_worktree_error(&error_message) {
+                    return GitResponse {
+                        name: local_repo.name,
+                        message: "not pulled, local changes would be overwritten".to_string(),
+                        state: State::NotPulled,
+                    };
+                }
+                if context.heal && git::is_corruption_error(&error_message) {
+                    return heal_repo(&local_repo, error_message, &context).await;
+                }
+                if git::is_unsafe_directory_error(&error_message) {
+                    context.record_failure(false, &error_message);
+                    return GitResponse {
+                        name: local_repo.name,
+                        message: error_message,
+                        state: State::UnsafeDirectory,
+                    };
+                }
+                let is_credential_error = git::is_credential_error(&error_message);
+                context.record_failure(
+                    is_credential_error || git::is_auth_error(&error_message),
+                    &error_message,
+                );
+                return GitResponse {
+                    name: local_repo.name,
+                    message: format!(
+                        "Error message: {}\nInfo message: {}",
+                        error_message, info_message
+                    ),
+                    state: if is_credential_error {
+                        State::CredentialError
+                    } else {
+                        State::PullError
+                    },
+                };
+            }
+
+            context.auth_failures.record_success();
+            if context.verify_pulls
+                && let Err(fsck_message) = git::git_fsck(&local_repo.path).await
+            {
+                if context.heal {
+                    return heal_repo(&local_repo, fsck_message, &context).await;
+                }
+                return corrupt_repo(local_repo.name, fsck_message, &context);
+            }
+            if let Some(pinned_branch) = pinned_branch
+                && let Some(count) =
+                    git::commits_ahead_of_remote(&local_repo.path, pinned_branch).await
+                && count > 0
+            {
+                return GitResponse {
+                    name: local_repo.name,
+                    message: format!(
+                        "diverged from the pinned {}, {} local commit(s) that are not on {}/{}",
+                        pinned_branch,
+                        count,
+                        git::remote_name(&local_repo.path),
+                        pinned_branch
+                    ),
+                    state: State::PinDiverged,
+                };
+            }
+            if let Some((default_branch, count)) =
+                git::local_commits_on_default(&local_repo.path).await
+                && count > 0
+            {
+                return GitResponse {
+                    name: local_repo.name,
+                    message: format!(
+                        "{} local commit(s) on {} that are not on {}/{}",
+                        count,
+                        default_branch,
+                        git::remote_name(&local_repo.path),
+                        default_branch
+                    ),
+                    state: State::LocalCommitsOnDefault,
+                };
+            }
+            if context.verify_signatures
+                && let Err(verify_message) =
+                    git::verify_head_signature(&local_repo.path, context.allowed_signers.as_ref())
+                        .await
+            {
+                return GitResponse {
+                    name: local_repo.name,
+                    message: verify_message,
+                    state: State::UnverifiedSignature,
+                };
+            }
+            let warnings = git::pull_warnings(
+                &local_repo.path,
+                context.shallow_clones,
+                pinned_branch.is_some(),
+            )
+            .await;
+            if git::is_up_to_date(&info_message) {
+                if !warnings.is_empty() {
+                    return GitResponse {
+                        name: local_repo.name,
+                        message: warnings.join(", "),
+                        state: State::Warning,
+                    };
+                }
+                return GitResponse {
+                    name: local_repo.name,
+                    message: "".into(),
+                    state: State::PullNoOp,
+                };
+            }
+
+            if context.record_events
+                && let Some(old_head) = &old_head
+            {
+                let commits = git::git_log_range(&local_repo.path, old_head, "HEAD").await;
+                events::append(&local_repo.name, "updated", commits.unwrap_or_default());
+            }
+            let diffstat = match old_head.filter(|_| context.show_diffstat) {
+                Some(old_head) => {
+                    git::git_diffstat(&local_repo.path, &old_head, "HEAD", style::supports_ansi())
+                        .await
+                }
+                None => None,
+            };
+            if !warnings.is_empty() {
+                return GitResponse {
+                    name: local_repo.name,
+                    message: format!("updated, but {}", warnings.join(", ")),
+                    state: State::Warning,
+                };
+            }
+            return GitResponse {
+                name: local_repo.name,
+                message: diffstat.unwrap_or(info_message),
+                state: State::Updated,
+            };
+        }
+    };
+}
+
+// a clone that follows releases is up to date at the newest tag, a tag that can't be
+// checked out is a warning and leaves the clone where it is.
+fn release_response(
+    name: String,
+    moved: Result<Option<String>, String>,
+    moved_state: State,
+    unmoved_state: State,
+) -> GitResponse {
+    match moved {
+        Ok(Some(tag)) => GitResponse {
+            name,
+            message: format!("now at {}", tag),
+            state: moved_state,
+        },
+        Ok(None) => GitResponse {
+            name,
+            message: "".into(),
+            state: unmoved_state,
+        },
+        Err(message) => GitResponse {
+            name,
+            message,
+            state: State::Warning,
+        },
+    }
+}
+
+// a new team repo with the directory it is cloned into and its extra clone args.
+type ClonePlan = (RemoteRepo, String, Vec<String>);
+
+fn handle_new_clone(
+    repo_root_dir: &PathBuf,
+    clone_plan: ClonePlan,
+    priority: bool,
+    progress: PhaseProgress,
+    multi_progress_bar: MultiProgress,
+    repo_progress_style: ProgressStyle,
+    context: SyncContext,
+) -> impl Future<Output = GitResponse> + Send + 'static {
+    let repo_root_dir_clone = repo_root_dir.clone();
+    let (new_repo, dir_name, clone_args) = clone_plan;
+
+    async move {
+        if context.cancellation.is_cancelled() {
+            return skipped(new_repo.name, &progress);
+        }
+        if !priority && context.past_deadline(None) {
+            return deferred(new_repo.name, &progress);
+        }
+        let clone_path = repo_root_dir_clone.join(&dir_name);
+        let follows_releases = context.release_repos.contains(&dir_name);
+        let _lock = repo_lock::lock(&clone_path).await;
+        // another sync cloned it while this one waited for the lock.
+        if clone_path.exists() {
+            return skipped(new_repo.name, &progress);
+        }
+        progress.start("clone", &new_repo.name);
+        let repo_progress_bar = multi_progress_bar.add(ProgressBar::new(100));
+        repo_progress_bar.set_style(repo_progress_style);
+        repo_progress_bar.set_prefix(new_repo.name.clone());
+        let mut downloaded: Option<u64> = None;
+        let clone = git::git_clone(
+            &new_repo,
+            repo_root_dir_clone,
+            dir_name,
+            &clone_args,
+            &context.protocol,
+            |clone_progress| {
+                downloaded = clone_progress.bytes.or(downloaded);
+                repo_progress_bar.set_message(clone_progress.phase);
+                repo_progress_bar.set_position(clone_progress.percent);
+            },
+        );
+        // dropping the clone on timeout kills git and removes the partial clone.
+        let result = match context.clone_timeout {
+            Some(clone_timeout) => match tokio::time::timeout(clone_timeout, clone).await {
+                Ok(result) => result,
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!(
+                        "clone timed out after {}",
+                        time_format::duration(clone_timeout)
+                    ),
+                )),
+            },
+            None => clone.await,
+        };
+        if let Some(bytes) = downloaded {
+            context.record_download(&new_repo.name, bytes);
+        }
+        repo_progress_bar.finish_and_clear();
+        multi_progress_bar.remove(&repo_progress_bar);
+        progress.finish("clone", &new_repo.name);
+        let _ = match result {
+            Ok(output) if !output.status.success() => {
+                let error_message = String::from_utf8_lossy(output.stderr.trim_ascii()).to_string();
+                let is_credential_error = git::is_credential_error(&error_message);
+                context.record_failure(
+                    is_credential_error || git::is_auth_error(&error_message),
+                    &error_message,
+                );
+                return GitResponse {
+                    name: new_repo.name,
+                    message: error_message,
+                    state: if is_credential_error {
+                        State::CredentialError
+                    } else {
+                        State::CloneError
+                    },
+                };
+            }
+            Ok(_) => {
+                context.auth_failures.record_success();
+                if context.verify_clones
+                    && let Err(fsck_message) = git::git_fsck(&clone_path).await
+                {
+                    return corrupt_repo(new_repo.name, fsck_message, &context);
+                }
+                if context.record_events {
+                    events::append(&new_repo.name, "cloned", Vec::new());
+                }
+                if follows_releases {
+                    let moved = git::checkout_latest_release(&clone_path).await;
+                    return release_response(new_repo.name, moved, State::Cloned, State::Cloned);
+                }
+                return GitResponse {
+                    name: new_repo.name,
+                    message: "".into(),
+                    state: State::Cloned,
+                };
+            }
+            Err(message) => {
+                context.record_failure(false, &message.to_string());
+                return GitResponse {
+                    name: new_repo.name,
+                    message: message.to_string(),
+                    state: State::CloneError,
+                };
+            }
+        };
+    }
+}
+
+// safe.directory compares the absolute path, entries that are already there are kept.
+fn trust_repos_of_other_users(local_repos: &Vec<LocalRepo>) {
+    let safe_directories = git::safe_directories();
+    for local_repo in local_repos {
+        if !git::is_owned_by_other_user(&local_repo.path) {
+            continue;
+        }
+        let path = std::fs::canonicalize(&local_repo.path).unwrap_or(local_repo.path.clone());
+        if safe_directories.contains(&path.to_string_lossy().to_string()) {
+            continue;
+        }
+        match git::add_safe_directory(&path) {
+            Ok(output) if output.status.success() => {
+                println!("added {} to safe.directory", path.display())
+            }
+            Ok(output) => println!(
+                "{}: {}",
+                style::paint("failed to add safe.directory", style::ERROR),
+                String::from_utf8_lossy(output.stderr.trim_ascii())
+            ),
+            Err(e) => println!(
+                "{}: {}",
+                style::paint("failed to add safe.directory", style::ERROR),
+                e
+            ),
+        }
+    }
+}
+
+// before the first clone or fetch, of syncs and of the cache and submodules commands.
+pub fn configure_transport(cli: &ArgMatches, token: &str) {
+    let protocol = cli.get_one::<String>("protocol").unwrap();
+    if protocol == "https" {
+        git::disable_terminal_prompt();
+        if cli.get_flag("token_credentials") {
+            git::use_token_credentials(token);
+        } else if git::credential_helper().is_none() {
+            match cli.get_one::<String>("credential_helper") {
+                Some(helper) => match git::set_global_credential_helper(helper) {
+                    Ok(output) if output.status.success() => {
+                        println!("configured git credential.helper {}", helper)
+                    }
+                    Ok(output) => println!(
+                        "{}: {}",
+                        style::paint("failed to configure credential.helper", style::ERROR),
+                        String::from_utf8_lossy(output.stderr.trim_ascii())
+                    ),
+                    Err(e) => println!(
+                        "{}: {}",
+                        style::paint("failed to configure credential.helper", style::ERROR),
+                        e
+                    ),
+                },
+                None => println!(
+                    "{}: https clones and pulls will fail. Rerun with --credential-helper <helper> or --token-credentials.",
+                    style::paint("no git credential.helper configured", style::WARNING)
+                ),
+            }
+        }
+    }
+
+    if protocol == "ssh" && !ssh::is_known_host(ssh::GITHUB_HOST) {
+        if cli.get_flag("accept_hostkeys") {
+            match ssh::add_github_host_keys() {
+                Ok(path) => println!("added {} host keys to {}", ssh::GITHUB_HOST, path.display()),
+                Err(e) => println!(
+                    "{}: {}",
+                    style::paint(
+                        &format!("failed to add {} host keys", ssh::GITHUB_HOST),
+                        style::ERROR
+                    ),
+                    e
+                ),
+            }
+        } else {
+            println!(
+                "{}: clones may hang on the host key prompt. Rerun with --accept-hostkeys to add the published host keys.",
+                style::paint(
+                    &format!("{} is not in ~/.ssh/known_hosts", ssh::GITHUB_HOST),
+                    style::WARNING
+                )
+            );
+        }
+    }
+}
+
+pub async fn run(
+    cli: &ArgMatches,
+    config: &config::Config,
+    provider: &dyn Provider,
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    github_team_prefix: &String,
+    repo_root_dir: &PathBuf,
+) {
+    let directories = state::load().directories;
+    let pinned_branches = config.pinned_branches(github_team_prefix, &directories);
+    let release_repos = config.release_repos(github_team_prefix, &directories);
+    let provider_name = cli.get_one::<String>("provider").unwrap();
+    let protocol = cli.get_one::<String>("protocol").unwrap();
+    let language = cli.get_one::<String>("language");
+    let selected_repos: Vec<String> = match cli.subcommand() {
+        Some(("sync", sync)) => sync
+            .get_many::<String>("repos")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let multi_progress_bar = if progress::mode() != progress::Mode::Bars {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    };
+    let spinner_style = ProgressStyle::with_template(
+        "{prefix:.bold.dim} {pos:>7}/{len:7} {elapsed:>4} eta {eta:<4} {per_sec:>10} {wide_msg}",
+    )
+    .unwrap();
+    let repo_progress_style =
+        ProgressStyle::with_template("  {prefix:30!} {bar:20} {pos:>3}% {msg}").unwrap();
+
+    let cancellation = CancellationToken::default();
+    let context = SyncContext {
+        auth_failures: Arc::new(AuthFailureDetector::new(
+            AUTH_FAILURE_THRESHOLD,
+            cancellation.clone(),
+        )),
+        cancellation,
+        fail_fast: cli.get_flag("fail_fast"),
+        show_diffstat: cli.get_flag("show_diffstat"),
+        pull_options: git::PullOptions {
+            prune: cli.get_flag("prune"),
+            prune_tags: cli.get_flag("prune_tags"),
+            deepen_on_demand: cli.get_flag("deepen_on_demand"),
+        },
+        shallow_clones: cli.contains_id("shallow_since"),
+        quick: cli.get_flag("quick"),
+        pinned_branches: Arc::new(pinned_branches),
+        release_repos: Arc::new(release_repos),
+        deadline: cli
+            .get_one::<Duration>("deadline")
+            .map(|deadline| Instant::now() + *deadline),
+        verify_signatures: cli.get_flag("verify_signatures"),
+        allowed_signers: cli.get_one::<PathBuf>("allowed_signers").cloned(),
+        verify_clones: cli.get_flag("verify"),
+        verify_pulls: cli.get_flag("verify_pulls"),
+        heal: cli.get_flag("heal"),
+        protocol: protocol.clone(),
+        clone_timeout: cli.get_one::<Duration>("clone_timeout").copied(),
+        record_events: cli.get_flag("events"),
+        downloaded: Arc::new(Mutex::new(BTreeMap::new())),
+    };
+
+    let run_started = Instant::now();
+    let mut sync_state = state::load();
+    children::forward_signals();
+
+    // pulls and clones are planned up front and run through one queue with one bar.
+    let progress = PhaseProgress::new(multi_progress_bar.add(ProgressBar::no_length()));
+    let progress_bar = progress.bar.clone();
+    progress_bar.set_style(spinner_style.clone());
+    set_phase(&progress_bar, "looking for team repos...");
+    let remote_repos = provider::list_remote_repos(provider).await;
+    set_phase(&progress_bar, "gathering local repos...");
+    let local_repos = list_local_repos(&repo_root_dir);
+    let selected_local_repos: Vec<LocalRepo> = local_repos
+        .iter()
+        // clones are selected by their directory or their team repo name, like new repos.
+        .filter(|local_repo| {
+            let team_repo_name = remote_repos
+                .iter()
+                .find(|remote_repo| {
+                    git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                        == local_repo.name
+                })
+                .map(|remote_repo| remote_repo.name.as_str());
+            is_selected(
+                &selected_repos,
+                &[&local_repo.name, team_repo_name.unwrap_or_default()],
+            )
+        })
+        .filter(|local_repo| !sync_state.unmanaged.contains(&local_repo.name))
+        .filter(|local_repo| match language {
+            Some(language) => remote_repos.iter().any(|remote_repo| {
+                remote_repo.has_language(language)
+                    && git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                        == local_repo.name
+            }),
+            None => true,
+        })
+        .cloned()
+        .collect();
+    let limit = cli.get_one::<usize>("limit").copied();
+    let sample = cli.get_one::<usize>("sample").copied();
+    let mut selected_local_repos =
+        sample::restrict(selected_local_repos, limit, sample, |local_repo| {
+            &local_repo.name
+        });
+    // the longest pulls of earlier runs start first, so no slow repo is left running alone at
+    // the end. repos without recorded durations follow, recently pushed ones first and repos
+    // unknown to the API last.
+    selected_local_repos.sort_by_cached_key(|local_repo| {
+        let team_repo = remote_repos.iter().find(|remote_repo| {
+            git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                == local_repo.name
+        });
+        let expected_duration = sync_state
+            .history
+            .get(team_repo.map_or(&local_repo.name, |remote_repo| &remote_repo.name))
+            .and_then(|outcomes| health::expected_duration(outcomes));
+        let pushed_at = team_repo.and_then(|remote_repo| remote_repo.pushed_at.clone());
+        (
+            std::cmp::Reverse(expected_duration),
+            std::cmp::Reverse(pushed_at),
+        )
+    });
+    // clones only get what is left of --limit or --sample after the pulls.
+    let remaining =
+        |limit: Option<usize>| limit.map(|limit| limit.saturating_sub(selected_local_repos.len()));
+    let (clone_limit, clone_sample) = (remaining(limit), remaining(sample));
+
+    set_phase(&progress_bar, "looking for new team repos...");
+    let github_active_team_repos = git::list_active_github_team_repos(remote_repos.clone()).await;
+    // explicitly selected repos are cloned even when they are ignored.
+    let (mut ignored_repos, new_repos): (Vec<RemoteRepo>, Vec<RemoteRepo>) = git::find_new_repos(
+        &github_active_team_repos,
+        &local_repos,
+        &github_team_prefix,
+        &sync_state.directories,
+    )
+    .into_iter()
+    .filter(|new_repo| {
+        let dir_name = git::local_dir_name(new_repo, github_team_prefix, &sync_state.directories);
+        is_selected(&selected_repos, &[&new_repo.name, &dir_name])
+    })
+    .filter(|new_repo| language.is_none_or(|language| new_repo.has_language(language)))
+    .partition(|new_repo| {
+        let dir_name = git::local_dir_name(new_repo, github_team_prefix, &sync_state.directories);
+        selected_repos.is_empty() && sync_state.is_ignored(&[&new_repo.name, &dir_name])
+    });
+    let mut new_repos = sample::restrict(new_repos, clone_limit, clone_sample, |new_repo| {
+        &new_repo.name
+    });
+    let mut state_changed = false;
+    let mut adopted_repos: Vec<(RemoteRepo, LocalRepo)> = Vec::new();
+    if cli.get_flag("adopt") && !new_repos.is_empty() {
+        for (remote_repo, local_repo) in adopt::find_candidates(
+            &new_repos,
+            &local_repos,
+            &remote_repos,
+            github_team_prefix,
+            &sync_state.directories,
+        ) {
+            let question = format!(
+                "{} already has origin {}, adopt it instead of cloning?",
+                local_repo.name, remote_repo.name
+            );
+            if multi_progress_bar.suspend(|| prompt::confirm(&question)) {
+                sync_state
+                    .directories
+                    .insert(remote_repo.name.clone(), local_repo.name.clone());
+                new_repos.retain(|new_repo| new_repo.name != remote_repo.name);
+                adopted_repos.push((remote_repo, local_repo));
+                state_changed = true;
+            }
+        }
+    }
+    if cli.get_flag("interactive") && !new_repos.is_empty() {
+        let (accepted, declined): (Vec<RemoteRepo>, Vec<RemoteRepo>) =
+            new_repos.into_iter().partition(|new_repo| {
+                multi_progress_bar
+                    .suspend(|| prompt::confirm(&format!("clone new team repo {}?", new_repo.name)))
+            });
+        for declined_repo in &declined {
+            sync_state.ignored.insert(declined_repo.name.clone());
+            state_changed = true;
+        }
+        new_repos = accepted;
+        ignored_repos.extend(declined);
+    }
+    // clones of repos that left the team are not pulled as team repos until it is decided
+    // whether reposync keeps pulling them.
+    let mut transferred_out: Vec<GitResponse> = Vec::new();
+    if provider_name == "github" {
+        set_phase(&progress_bar, "looking for repos moved out of the team...");
+        for transferred_repo in transferred::find(
+            api_client,
+            github_team_repo_url,
+            &selected_local_repos,
+            &remote_repos,
+            github_team_prefix,
+            &sync_state,
+        )
+        .await
+        {
+            let name = transferred_repo.local_repo.name;
+            let decision = if cli.get_flag("interactive") {
+                multi_progress_bar.suspend(|| {
+                    prompt::ask(
+                        &format!(
+                            "{} moved out of the team to {}, stop managing it, keep pulling it as external or decide later? [stop/keep/later]",
+                            name, transferred_repo.full_name
+                        ),
+                        "later",
+                    )
+                })
+            } else {
+                "later".to_string()
+            };
+            match decision.as_str() {
+                "stop" => {
+                    sync_state.unmanaged.insert(name.clone());
+                    state_changed = true;
+                }
+                "keep" => {
+                    sync_state.external.insert(name);
+                    state_changed = true;
+                    continue;
+                }
+                _ => transferred_out.push(GitResponse {
+                    name: name.clone(),
+                    message: format!("moved out of the team to {}", transferred_repo.full_name),
+                    state: State::TransferredOut,
+                }),
+            }
+            selected_local_repos.retain(|local_repo| local_repo.name != name);
+        }
+    }
+    // a classify script decides the directory, extra clone args or skipping per new repo.
+    let script =
+        cli.get_one::<PathBuf>("classify_script")
+            .map(|path| match script::Script::load(path) {
+                Ok(script) => script,
+                Err(e) => {
+                    println!(
+                        "{}: {}",
+                        style::paint(&format!("failed to load {}", path.display()), style::ERROR),
+                        e
+                    );
+                    std::process::exit(1);
+                }
+            });
+    let mut clone_plans: Vec<ClonePlan> = Vec::new();
+    for new_repo in new_repos {
+        let classification = match &script {
+            Some(script) => script.classify(&new_repo).unwrap_or_else(|e| {
+                println!(
+                    "{}: classify failed, using defaults: {}",
+                    style::paint(&new_repo.name, style::ERROR),
+                    e
+                );
+                script::Classification::default()
+            }),
+            None => script::Classification::default(),
+        };
+        if classification.skip {
+            ignored_repos.push(new_repo);
+            continue;
+        }
+        let dir_name = match classification.dir {
+            // remembered like an adopted clone, so later runs find the repo in its directory.
+            // only direct children of the repo root dir are found as local repos.
+            Some(dir_name) if !dir_name.contains(['/', '\\']) => {
+                sync_state
+                    .directories
+                    .insert(new_repo.name.clone(), dir_name.clone());
+                state_changed = true;
+                dir_name
+            }
+            _ => git::local_dir_name(&new_repo, github_team_prefix, &sync_state.directories),
+        };
+        let mut clone_args = classification.clone_args;
+        if let Err(e) = git_version::check_clone_args(&clone_args) {
+            println!(
+                "{}: classify clone args left out: {}",
+                style::paint(&new_repo.name, style::ERROR),
+                e
+            );
+            clone_args.clear();
+        }
+        if let Some(pinned_branch) = context
+            .pinned_branches
+            .get(&dir_name)
+            .or(context.pinned_branches.get(&new_repo.name))
+        {
+            clone_args.push(format!("--branch={}", pinned_branch));
+        }
+        if let Some(shallow_since) = cli.get_one::<Duration>("shallow_since") {
+            let since = chrono::DateTime::<chrono::Utc>::from(
+                std::time::SystemTime::now() - *shallow_since,
+            );
+            clone_args.push(format!(
+                "--shallow-since={}",
+                since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            ));
+        }
+        if let Some(reference_dir) = cli.get_one::<PathBuf>("reference_dir")
+            && let Some(reference) = git::reference_repo(reference_dir, &new_repo, &dir_name)
+        {
+            clone_args.push("--reference-if-able".to_string());
+            clone_args.push(reference.to_string_lossy().to_string());
+        }
+        clone_plans.push((new_repo, dir_name, clone_args));
+    }
+    if state_changed && let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+
+    if read_only::is_read_only() {
+        for (new_repo, dir_name, _) in &clone_plans {
+            println!(
+                "{}: would be cloned into {}",
+                style::paint(&new_repo.name, style::CLONED),
+                dir_name
+            );
+        }
+        println!(
+            "{}: {}",
+            style::paint("Would be pulled", style::OK),
+            selected_local_repos.len()
+        );
+        return;
+    }
+    if cli.get_flag("add_safe_directory") {
+        trust_repos_of_other_users(&selected_local_repos);
+    }
+    // fails before the first clone instead of halfway through with a full disk.
+    if let Some(message) = disk_space::check(
+        repo_root_dir,
+        // clones from the object cache only need space for the checkout.
+        disk_space::required_space(
+            clone_plans
+                .iter()
+                .filter(|(_, _, clone_args)| {
+                    !clone_args.iter().any(|arg| arg == "--reference-if-able")
+                })
+                .map(|(new_repo, _, _)| new_repo),
+        ),
+    ) {
+        if cli.get_flag("force") {
+            println!("{}", style::paint(&message, style::WARNING));
+        } else {
+            println!(
+                "{}: {}",
+                style::paint("not enough disk space", style::ERROR),
+                message
+            );
+            println!(
+                "  hint: free up space, clone fewer repos with --limit or rerun with --force."
+            );
+            std::process::exit(1);
+        }
+    }
+
+    // the masters are closed when this is dropped after the last repo.
+    let ssh_multiplex = if cli.get_flag("ssh_multiplex") && protocol == "ssh" {
+        set_phase(&progress_bar, "opening shared ssh connections...");
+        if cfg!(windows) {
+            println!(
+                "{}",
+                style::paint(
+                    "--ssh-multiplex is not supported on Windows, every repo uses its own connection",
+                    style::WARNING
+                )
+            );
+            None
+        } else {
+            match multi_progress_bar.suspend(|| {
+                ssh::Multiplex::start(remote_repos.iter().map(|remote_repo| &remote_repo.ssh_url))
+            }) {
+                Ok(multiplex) => {
+                    git::use_ssh_command(multiplex.ssh_command());
+                    Some(multiplex)
+                }
+                Err(e) => {
+                    println!(
+                        "{}: {}",
+                        style::paint("failed to set up ssh multiplexing", style::WARNING),
+                        e
+                    );
+                    None
+                }
+            }
+        }
+    } else {
+        None
+    };
+
+    set_phase(&progress_bar, "syncing repos...");
+    let total = selected_local_repos.len() + clone_plans.len();
+    progress_bar.set_length(total as u64);
+    // responses of pulls carry the directory, the team repo name comes along for the state.
+    let mut work_queue: queue::WorkQueue<(&'static str, String, GitResponse, Duration)> =
+        queue::WorkQueue::new(*cli.get_one::<usize>("jobs").unwrap());
+    let team_repo_of = |local_repo: &LocalRepo| {
+        remote_repos.iter().find(|remote_repo| {
+            git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                == local_repo.name
+        })
+    };
+    // the repos of the priority list in the config file start first, then the others.
+    let (priority_clones, other_clones): (Vec<_>, Vec<_>) = clone_plans
+        .into_iter()
+        .partition(|(new_repo, dir_name, _)| config.is_priority(&[&new_repo.name, dir_name]));
+    let (priority_pulls, other_pulls): (Vec<_>, Vec<_>) =
+        selected_local_repos.into_iter().partition(|local_repo| {
+            let team_repo_name =
+                team_repo_of(local_repo).map(|remote_repo| remote_repo.name.as_str());
+            config.is_priority(&[&local_repo.name, team_repo_name.unwrap_or_default()])
+        });
+    for (priority, clone_plans, local_repos) in [
+        (true, priority_clones, priority_pulls),
+        (false, other_clones, other_pulls),
+    ] {
+        // clones take longest, so they start first and the pulls fill the remaining slots.
+        for clone_plan in clone_plans {
+            let team_repo_name = clone_plan.0.name.clone();
+            let clone = handle_new_clone(
+                repo_root_dir,
+                clone_plan,
+                priority,
+                progress.clone(),
+                multi_progress_bar.clone(),
+                repo_progress_style.clone(),
+                context.clone(),
+            );
+            work_queue.push(async move {
+                let started = Instant::now();
+                ("clone", team_repo_name, clone.await, started.elapsed())
+            });
+        }
+        for local_repo in local_repos {
+            let team_repo = team_repo_of(&local_repo);
+            let pushed_since_last_sync = team_repo.is_none_or(|remote_repo| {
+                remote_repo.pushed_at.is_none()
+                    || remote_repo.pushed_at.as_ref() != sync_state.pushed_at.get(&remote_repo.name)
+            });
+            let team_repo_name = team_repo
+                .map(|remote_repo| remote_repo.name.clone())
+                .unwrap_or(local_repo.name.clone());
+            let expected_duration = sync_state
+                .history
+                .get(&team_repo_name)
+                .and_then(|outcomes| health::expected_duration(outcomes));
+            let pull = handle_new_pull(
+                local_repo,
+                pushed_since_last_sync,
+                expected_duration,
+                priority,
+                progress.clone(),
+                context.clone(),
+            );
+            work_queue.push(async move {
+                let started = Instant::now();
+                ("pull", team_repo_name, pull.await, started.elapsed())
+            });
+        }
+    }
+
+    let github_archived_team_repos =
+        git::list_archived_github_team_repos(remote_repos.clone()).await;
+    let archived_repos = git::find_archived_local_repos(
+        &github_archived_team_repos,
+        &local_repos,
+        &github_team_prefix,
+        &sync_state.directories,
+    );
+
+    let archive_action = cli.get_one::<String>("archive_action").unwrap();
+    let archive_grace_period = cli.get_one::<Duration>("archive_grace_period").unwrap();
+    let (archived_in_grace_period, archived_repos): (Vec<RemoteRepo>, Vec<RemoteRepo>) =
+        archived_repos.into_iter().partition(|archived_repo| {
+            archive::is_within_grace_period(archived_repo, *archive_grace_period)
+        });
+
+    let mut pull_errors: Vec<GitResponse> = Vec::new();
+    let mut pull_noop: Vec<GitResponse> = Vec::new();
+    let mut updated: Vec<GitResponse> = Vec::new();
+    let mut cloned: Vec<GitResponse> = Vec::new();
+    let mut clone_errors: Vec<GitResponse> = Vec::new();
+    let mut skipped_repos: Vec<GitResponse> = Vec::new();
+    let mut deferred_repos: Vec<GitResponse> = Vec::new();
+    let mut local_commits_on_default: Vec<GitResponse> = Vec::new();
+    let mut pin_diverged: Vec<GitResponse> = Vec::new();
+    let mut unverified_signatures: Vec<GitResponse> = Vec::new();
+    let mut corrupt_repos: Vec<GitResponse> = Vec::new();
+    let mut healed_repos: Vec<GitResponse> = Vec::new();
+    let mut credential_errors: Vec<GitResponse> = Vec::new();
+    let mut unsafe_directories: Vec<GitResponse> = Vec::new();
+    let mut warnings: Vec<GitResponse> = Vec::new();
+    let mut synced_repos: Vec<plugin::SyncedRepo> = Vec::new();
+    // team repo name to whether it was synced without failure or warning.
+    let mut synced_team_repos: BTreeMap<String, bool> = BTreeMap::new();
+    let mut finished = 0;
+    while let Some((operation, team_repo_name, result, duration)) = work_queue.next().await {
+        synced_repos.push(plugin::SyncedRepo {
+            name: result.name.clone(),
+            state: format!("{:?}", result.state),
+            duration_ms: duration.as_millis() as u64,
+        });
+        // one history per team repo, whether it was cloned or pulled from its directory.
+        match result.state {
+            State::Skipped | State::Deferred => {}
+            State::PullError
+            | State::CloneError
+            | State::CredentialError
+            | State::UnsafeDirectory
+            | State::CorruptRepo => health::record(
+                &mut sync_state.history,
+                &team_repo_name,
+                Some(&result.message),
+                duration,
+            ),
+            _ => health::record(&mut sync_state.history, &team_repo_name, None, duration),
+        }
+        synced_team_repos.insert(
+            team_repo_name,
+            matches!(
+                result.state,
+                State::PullNoOp | State::Updated | State::Cloned
+            ),
+        );
+        finished += 1;
+        let failed = matches!(
+            result.state,
+            State::PullError
+                | State::CloneError
+                | State::CredentialError
+                | State::UnsafeDirectory
+                | State::CorruptRepo
+        );
+        if progress::mode() == progress::Mode::Lines {
+            println!(
+                "[{}/{}] {} {}: {}",
+                finished,
+                total,
+                operation,
+                style::paint(
+                    &result.name,
+                    match result.state {
+                        _ if failed => style::ERROR,
+                        State::NotPulled | State::Warning => style::WARNING,
+                        _ => style::OK,
+                    }
+                ),
+                result.state.label()
+            );
+        } else if failed && progress::mode() == progress::Mode::Bars {
+            multi_progress_bar.suspend(|| {
+                println!(
+                    "[{}] {}: failed, details follow in the summary",
+                    operation,
+                    style::paint(&result.name, style::ERROR)
+                )
+            });
+        }
+        match result.state {
+            State::PullError => {
+                pull_errors.push(result);
+            }
+            State::CloneError => {
+                clone_errors.push(result);
+            }
+            State::CredentialError => {
+                credential_errors.push(result);
+            }
+            State::UnsafeDirectory => {
+                unsafe_directories.push(result);
+            }
+            State::PullNoOp => {
+                pull_noop.push(result);
+            }
+            State::Updated => {
+                updated.push(result);
+            }
+            State::Cloned => {
+                cloned.push(result);
+            }
+            State::Skipped => {
+                skipped_repos.push(result);
+            }
+            State::Deferred => {
+                deferred_repos.push(result);
+            }
+            State::LocalCommitsOnDefault => {
+                local_commits_on_default.push(result);
+            }
+            State::PinDiverged => {
+                pin_diverged.push(result);
+            }
+            State::TransferredOut => {
+                transferred_out.push(result);
+            }
+            State::UnverifiedSignature => {
+                unverified_signatures.push(result);
+            }
+            State::CorruptRepo => {
+                corrupt_repos.push(result);
+            }
+            State::Healed => {
+                healed_repos.push(result);
+            }
+            State::NotPulled | State::Warning => {
+                warnings.push(result);
+            }
+        };
+    }
+    drop(ssh_multiplex);
+    set_phase(&progress_bar, "syncing finished");
+    progress_bar.finish_and_clear();
+    // fresh clones are recorded too, later runs look them up instead of deriving the name again.
+    let synced_local_repos = list_local_repos(repo_root_dir);
+    for remote_repo in &remote_repos {
+        if let Some(local_repo) = git::find_local_repo(
+            remote_repo,
+            &synced_local_repos,
+            github_team_prefix,
+            &sync_state.directories,
+        ) {
+            match (
+                &remote_repo.pushed_at,
+                synced_team_repos.get(&remote_repo.name),
+            ) {
+                (Some(pushed_at), Some(true)) => {
+                    sync_state
+                        .pushed_at
+                        .insert(remote_repo.name.clone(), pushed_at.clone());
+                }
+                // failed repos and repos with warnings get a full check next time.
+                (_, Some(_)) => {
+                    sync_state.pushed_at.remove(&remote_repo.name);
+                }
+                _ => {}
+            }
+            sync_state
+                .directories
+                .insert(remote_repo.name.clone(), local_repo.name.clone());
+        }
+    }
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+
+    let downloaded = context.downloaded.lock().unwrap().clone();
+    // e.g. " (1.2 MiB)", empty when git reported no transfer for the repo.
+    let downloaded_suffix = |name: &str| {
+        downloaded
+            .get(name)
+            .map(|bytes| format!(" ({})", git::format_size(*bytes)))
+            .unwrap_or_default()
+    };
+    let compact = cli.get_flag("compact");
+    if compact {
+        println!(
+            "{}",
+            compact_summary(&[
+                ("up to date", &pull_noop, false),
+                ("updated", &updated, true),
+                ("cloned", &cloned, true),
+                ("skipped", &skipped_repos, false),
+                ("deferred", &deferred_repos, true),
+                ("with warnings", &warnings, true),
+                ("diverged from their pin", &pin_diverged, true),
+                ("moved out of the team", &transferred_out, true),
+            ])
+        );
+    } else {
+        println!(
+            "{}: {}",
+            style::paint("Pull no-op count", style::OK),
+            pull_noop.iter().count()
+        );
+        for updated_repo in &updated {
+            println!(
+                "{}: updated{}",
+                style::paint(&updated_repo.name, style::UPDATED),
+                downloaded_suffix(&updated_repo.name)
+            );
+            if context.show_diffstat {
+                for line in updated_repo.message.lines() {
+                    println!("  {}", line);
+                }
+            }
+        }
+        for cloned_repo in &cloned {
+            println!(
+                "{}: cloned{}",
+                style::paint(&cloned_repo.name, style::CLONED),
+                downloaded_suffix(&cloned_repo.name)
+            );
+        }
+    }
+    if !config.source.is_empty() || !config.external.is_empty() {
+        let mut source_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for remote_repo in &remote_repos {
+            *source_counts
+                .entry(remote_repo.source.as_deref().unwrap_or(provider_name))
+                .or_default() += 1;
+        }
+        let source_counts: Vec<String> = source_counts
+            .iter()
+            .map(|(source, count)| format!("{} from {}", count, source))
+            .collect();
+        println!(
+            "{}: {}",
+            style::paint("Team repos", style::OK),
+            source_counts.join(", ")
+        );
+    }
+    if !downloaded.is_empty() {
+        println!(
+            "{}: {}",
+            style::paint("Downloaded", style::OK),
+            git::format_size(downloaded.values().sum())
+        );
+    }
+    for (remote_repo, local_repo) in adopted_repos {
+        println!(
+            "{}: adopted {}",
+            style::paint(&remote_repo.name, style::WARNING),
+            local_repo.name
+        );
+    }
+    // archive actions run after all pulls finished, so no git process works in a moved repo.
+    for archived_repo in archived_repos {
+        let Some(local_repo) = git::find_local_repo(
+            &archived_repo,
+            &local_repos,
+            github_team_prefix,
+            &sync_state.directories,
+        ) else {
+            continue;
+        };
+        // report repeats on every run, the feed only gets the run that acted on the clone.
+        let acts = archive_action != "report"
+            && !(archive_action == "readonly"
+                && local_repo.path.join(archive::ARCHIVED_MARKER).exists());
+        match archive::apply_action(local_repo, archive_action) {
+            Ok(message) => {
+                if acts && cli.get_flag("events") {
+                    events::append(&archived_repo.name, "archived", Vec::new());
+                }
+                println!(
+                    "{}: {}",
+                    style::paint(&archived_repo.name, style::ARCHIVED),
+                    message
+                )
+            }
+            Err(message) => println!(
+                "{}: archived, {} failed: {}",
+                style::paint(&archived_repo.name, style::ERROR),
+                archive_action,
+                message
+            ),
+        }
+    }
+    if !ignored_repos.is_empty() {
+        println!(
+            "{}: {}",
+            style::paint("Ignored count", style::OK),
+            ignored_repos.len()
+        );
+    }
+    if !archived_in_grace_period.is_empty() {
+        println!(
+            "{}: {}",
+            style::paint("Archived within grace period", style::OK),
+            archived_in_grace_period.len()
+        );
+    }
+    if selected_repos.is_empty() {
+        for duplicate in duplicates::find_duplicates(&list_local_repos(repo_root_dir)) {
+            println!("{}: Duplicate", style::paint(&duplicate.url, style::ERROR));
+            for local_repo in &duplicate.local_repos {
+                println!("  {}", local_repo.name);
+            }
+            if cli.get_flag("interactive") {
+                duplicates::resolve(&duplicate);
+            }
+        }
+    }
+    if repo_root_dir.join(views::VIEWS_DIR).is_dir()
+        && let Err(e) = views::regenerate(
+            repo_root_dir,
+            &remote_repos,
+            github_team_prefix,
+            &sync_state,
+        )
+    {
+        println!(
+            "{}: {}",
+            style::paint("failed to update views", style::ERROR),
+            e
+        );
+    }
+    for local_commits_repo in &local_commits_on_default {
+        println!(
+            "{}: {}",
+            style::paint(&local_commits_repo.name, style::ERROR),
+            local_commits_repo.message
+        );
+    }
+    if !compact {
+        for pin_diverged_repo in &pin_diverged {
+            println!(
+                "{}: {}",
+                style::paint(&pin_diverged_repo.name, style::WARNING),
+                pin_diverged_repo.message
+            );
+        }
+        for transferred_repo in &transferred_out {
+            println!(
+                "{}: {}",
+                style::paint(&transferred_repo.name, style::WARNING),
+                transferred_repo.message
+            );
+        }
+        if !transferred_out.is_empty() {
+            println!(
+                "  hint: rerun with --interactive to stop managing them or to keep pulling them as external repos."
+            );
+        }
+    }
+    for unverified_repo in &unverified_signatures {
+        println!(
+            "{}: HEAD is not signed by a trusted key:",
+            style::paint(&unverified_repo.name, style::ERROR)
+        );
+        for line in unverified_repo.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    for healed_repo in healed_repos {
+        println!(
+            "{}: healed:",
+            style::paint(&healed_repo.name, style::WARNING)
+        );
+        for line in healed_repo.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    for corrupt_repo in &corrupt_repos {
+        println!(
+            "{}: repository is corrupt:",
+            style::paint(&corrupt_repo.name, style::ERROR)
+        );
+        for line in corrupt_repo.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    for credential_error in &credential_errors {
+        println!(
+            "{}: git has no usable https credentials",
+            style::paint(&credential_error.name, style::ERROR)
+        );
+    }
+    if !credential_errors.is_empty() {
+        println!(
+            "  hint: configure a credential helper with --credential-helper <helper> or rerun with --token-credentials."
+        );
+    }
+    for unsafe_directory in &unsafe_directories {
+        println!(
+            "{}: owned by another user, git refuses it until it is listed in safe.directory",
+            style::paint(&unsafe_directory.name, style::ERROR)
+        );
+    }
+    if !unsafe_directories.is_empty() {
+        println!(
+            "  hint: rerun with --add-safe-directory to trust the repos in the repo root dir."
+        );
+    }
+    if !skipped_repos.is_empty() && !compact {
+        println!(
+            "{}: {}",
+            style::paint("Skipped count", style::WARNING),
+            skipped_repos.len()
+        );
+    }
+    if !deferred_repos.is_empty() && !compact {
+        println!(
+            "{}: {}",
+            style::paint("Deferred by the deadline", style::WARNING),
+            deferred_repos
+                .iter()
+                .map(|deferred_repo| deferred_repo.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+    if !warnings.is_empty() && !compact {
+        for warning in &warnings {
+            println!(
+                "{}: {}",
+                style::paint(&warning.name, style::WARNING),
+                warning.message
+            );
+        }
+        println!(
+            "{}: {}",
+            style::paint("Warning count", style::WARNING),
+            warnings.len()
+        );
+    }
+    let flaky_repos = health::flaky(&sync_state.history);
+    if !flaky_repos.is_empty() {
+        println!("{}:", style::paint("Flaky repos", style::WARNING));
+    }
+    for (name, outcomes) in flaky_repos {
+        let failures: Vec<&health::Outcome> =
+            outcomes.iter().filter(|outcome| outcome.failed).collect();
+        println!(
+            "{}: {} of the last {} runs failed, health {:.0}%",
+            style::paint(name, style::WARNING),
+            failures.len(),
+            outcomes.len(),
+            health::health(outcomes) * 100.0
+        );
+        for failure in failures.iter().rev().take(3) {
+            println!(
+                "  {} {}",
+                time_format::timestamp_str(&failure.time),
+                failure.error.as_deref().unwrap_or("")
+            );
+        }
+    }
+    // identical auth errors are summarized once instead of repeating them per repo.
+    let auth_diagnosis = context.auth_failures.diagnosis();
+    if let Some(diagnosis) = &auth_diagnosis {
+        println!("{}:", style::paint("authentication failed", style::ERROR));
+        for line in diagnosis.lines() {
+            println!("  {}", line);
+        }
+    }
+    let has_errors = !clone_errors.is_empty()
+        || !pull_errors.is_empty()
+        || !credential_errors.is_empty()
+        || !unsafe_directories.is_empty()
+        || !corrupt_repos.is_empty();
+    let failed_repos: Vec<FailedRepo> = clone_errors
+        .iter()
+        .map(|clone_error| FailedRepo {
+            name: &clone_error.name,
+            operation: "clone",
+            output: &clone_error.message,
+        })
+        .chain(pull_errors.iter().map(|pull_error| FailedRepo {
+            name: &pull_error.name,
+            operation: "pull",
+            output: &pull_error.message,
+        }))
+        .chain(credential_errors.iter().map(|credential_error| FailedRepo {
+            name: &credential_error.name,
+            operation: "authenticate",
+            output: &credential_error.message,
+        }))
+        .chain(
+            unsafe_directories
+                .iter()
+                .map(|unsafe_directory| FailedRepo {
+                    name: &unsafe_directory.name,
+                    operation: "pull",
+                    output: &unsafe_directory.message,
+                }),
+        )
+        .chain(corrupt_repos.iter().map(|corrupt_repo| FailedRepo {
+            name: &corrupt_repo.name,
+            operation: "verify",
+            output: &corrupt_repo.message,
+        }))
+        .collect();
+    // repos that didn't fail but need a look, e.g. for --fail-on warning.
+    let has_warnings = [
+        &warnings,
+        &pin_diverged,
+        &transferred_out,
+        &local_commits_on_default,
+        &unverified_signatures,
+    ]
+    .iter()
+    .any(|repos| !repos.is_empty());
+    let failure_log_dir = if failed_repos.is_empty() {
+        None
+    } else {
+        Some(failure_log::write_failure_logs(&failed_repos))
+    };
+    let rerun_command = failure_log::rerun_command(&failed_repos);
+    if let Some(report_path) = cli.get_one::<PathBuf>("report") {
+        let environment = report::capture_environment(cli, config);
+        match report::write(report_path, &environment, &synced_repos, &failed_repos) {
+            Ok(written) => println!("report: {}", written.display()),
+            Err(e) => println!(
+                "{}: {}",
+                style::paint("failed to write report", style::ERROR),
+                e
+            ),
+        }
+    }
+    if cli.get_flag("copy_failures") && !failed_repos.is_empty() {
+        match clipboard::copy(&failure_log::markdown_summary(&failed_repos)) {
+            Ok(()) => println!("copied the failed repos to the clipboard"),
+            Err(e) => println!(
+                "{}: {}",
+                style::paint("failed to copy to the clipboard", style::ERROR),
+                e
+            ),
+        }
+    }
+    let counts = [
+        ("up to date", pull_noop.len()),
+        ("updated", updated.len()),
+        ("cloned", cloned.len()),
+        ("skipped", skipped_repos.len()),
+        ("with warnings", warnings.len()),
+        ("diverged from their pin", pin_diverged.len()),
+        ("failed", failed_repos.len()),
+    ];
+    if cli.get_flag("email") {
+        match &config.email {
+            Some(email_settings) if email_settings.only_failures && failed_repos.is_empty() => {}
+            Some(email_settings) => {
+                if let Err(e) = email::send(
+                    email_settings,
+                    &email::subject(&counts),
+                    &email::body(&counts, &failed_repos),
+                ) {
+                    println!(
+                        "{}: {}",
+                        style::paint("failed to send the email", style::ERROR),
+                        e
+                    );
+                }
+            }
+            // the daemon turns --email on for every sync, also without an email table.
+            None if cli.value_source("email") != Some(clap::parser::ValueSource::CommandLine) => {}
+            None => println!(
+                "{}: no email table in the config file",
+                style::paint("failed to send the email", style::ERROR)
+            ),
+        }
+    }
+    if cli.get_one::<String>("output").map(String::as_str) == Some("github-actions") {
+        for failed_repo in &failed_repos {
+            github_actions::annotate(
+                "error",
+                failed_repo.name,
+                &format!(
+                    "{} failed: {}",
+                    failed_repo.operation,
+                    failure_log::excerpt(failed_repo.output)
+                ),
+            );
+        }
+        for warning in warnings
+            .iter()
+            .chain(&pin_diverged)
+            .chain(&transferred_out)
+            .chain(&local_commits_on_default)
+        {
+            github_actions::annotate("warning", &warning.name, &warning.message);
+        }
+        for unverified_repo in &unverified_signatures {
+            github_actions::annotate(
+                "warning",
+                &unverified_repo.name,
+                "HEAD is not signed by a trusted key",
+            );
+        }
+        for updated_repo in &updated {
+            github_actions::annotate("notice", &updated_repo.name, "updated");
+        }
+        for cloned_repo in &cloned {
+            github_actions::annotate("notice", &cloned_repo.name, "cloned");
+        }
+        if let Err(e) = github_actions::write_job_summary(&counts, &failed_repos) {
+            println!(
+                "{}: {}",
+                style::paint("failed to write the job summary", style::ERROR),
+                e
+            );
+        }
+    }
+    if let Some(status_file) = cli
+        .get_one::<PathBuf>("status_file")
+        .cloned()
+        .or_else(last_run::default_path)
+    {
+        let last_run = LastRun {
+            finished_at: chrono::Local::now().to_rfc3339(),
+            duration_secs: run_started.elapsed().as_secs(),
+            up_to_date: pull_noop.len(),
+            updated: updated.len(),
+            cloned: cloned.len(),
+            skipped: skipped_repos.len(),
+            behind: deferred_repos.len()
+                + warnings
+                    .iter()
+                    .filter(|warning| matches!(warning.state, State::NotPulled))
+                    .count(),
+            ahead: local_commits_on_default.len(),
+            warnings: warnings.len()
+                + pin_diverged.len()
+                + transferred_out.len()
+                + unverified_signatures.len(),
+            failed: failed_repos.len(),
+            failures: LastRun::failures(&failed_repos),
+        };
+        if let Err(e) = last_run::write(&status_file, &last_run) {
+            println!(
+                "{}: {}",
+                style::paint("failed to write the status file", style::ERROR),
+                e
+            );
+        }
+    }
+    drop(failed_repos);
+    for clone_error in clone_errors {
+        if auth_diagnosis.is_some() && git::is_auth_error(&clone_error.message) {
+            continue;
+        }
+        println!(
+            "{}: failed to clone:",
+            style::paint(&clone_error.name, style::ERROR)
+        );
+        for line in clone_error.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    for pull_error in pull_errors {
+        if auth_diagnosis.is_some() && git::is_auth_error(&pull_error.message) {
+            continue;
+        }
+        println!(
+            "{}: failed to pull:",
+            style::paint(&pull_error.name, style::ERROR)
+        );
+        for line in pull_error.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    match failure_log_dir {
+        Some(Ok(log_dir)) => {
+            println!("full git output of failed repos: {}", log_dir.display());
+            println!("retry failed repos: {}", rerun_command);
+        }
+        Some(Err(e)) => {
+            println!("failed to write failure logs: {}", e);
+            println!("retry failed repos: {}", rerun_command);
+        }
+        None => (),
+    }
+    for action in cli.get_many::<String>("action").unwrap_or_default() {
+        match plugin::run_action(action, &repo_root_dir.to_string_lossy(), &synced_repos) {
+            Ok(output) => print!("{}", output),
+            Err(e) => println!(
+                "{}: {}",
+                style::paint(&format!("action {} failed", action), style::ERROR),
+                e
+            ),
+        }
+    }
+    let fails = match cli.get_one::<String>("fail_on").map(|level| level.as_str()) {
+        Some("warning") => has_errors || has_warnings,
+        Some(_) => has_errors,
+        None => context.fail_fast && has_errors,
+    };
+    if fails {
+        std::process::exit(1);
+    }
+}
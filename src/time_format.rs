@@ -0,0 +1,99 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::TimeDelta;
+use chrono::Utc;
+
+// relative times read best for people, --utc and --iso give absolute ones for scripts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeFormat {
+    Relative,
+    Utc,
+    Iso,
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(TimeFormat::Relative as u8);
+
+pub fn set_format(format: TimeFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+fn format() -> TimeFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        value if value == TimeFormat::Utc as u8 => TimeFormat::Utc,
+        value if value == TimeFormat::Iso as u8 => TimeFormat::Iso,
+        _ => TimeFormat::Relative,
+    }
+}
+
+// e.g. 3 days ago, 2024-05-01 12:00:00 UTC with --utc or 2024-05-01T12:00:00Z with --iso.
+pub fn timestamp(time: DateTime<Utc>) -> String {
+    match format() {
+        TimeFormat::Relative => relative(Utc::now().signed_duration_since(time)),
+        TimeFormat::Utc => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        TimeFormat::Iso => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+// RFC 3339 as written by GitHub, git and the state files, anything else is printed as is.
+pub fn timestamp_str(time: &str) -> String {
+    match DateTime::parse_from_rfc3339(time) {
+        Ok(time) => timestamp(time.with_timezone(&Utc)),
+        Err(_) => time.to_string(),
+    }
+}
+
+pub fn system_time(time: SystemTime) -> String {
+    timestamp(DateTime::<Utc>::from(time))
+}
+
+// english units and digits only, nothing depends on the locale of the machine.
+fn relative(age: TimeDelta) -> String {
+    let seconds = age.num_seconds();
+    if seconds.abs() < 60 {
+        return "just now".to_string();
+    }
+    let span = span(seconds.unsigned_abs());
+    if seconds < 0 {
+        format!("in {}", span)
+    } else {
+        format!("{} ago", span)
+    }
+}
+
+// the largest unit that fits, e.g. 3 days for 80 hours.
+fn span(seconds: u64) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let (count, unit) = UNITS
+        .iter()
+        .find(|(unit_seconds, _)| seconds >= *unit_seconds)
+        .map(|(unit_seconds, unit)| (seconds / unit_seconds, *unit))
+        .unwrap_or((seconds, "second"));
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+// e.g. 2m 5s, or PT125S with --utc or --iso.
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if format() != TimeFormat::Relative {
+        return format!("PT{}S", seconds);
+    }
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, seconds) => format!("{}s", seconds),
+        (0, minutes, 0) => format!("{}m", minutes),
+        (0, minutes, seconds) => format!("{}m {}s", minutes, seconds),
+        (hours, 0, _) => format!("{}h", hours),
+        (hours, minutes, _) => format!("{}h {}m", hours, minutes),
+    }
+}
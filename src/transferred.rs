@@ -0,0 +1,75 @@
+use reqwest::Url;
+
+use crate::api;
+use crate::api::ApiClient;
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::state::SyncState;
+
+// a clone whose repo is gone from the team list but still exists on GitHub, e.g. after a
+// transfer to another team or owner.
+pub struct TransferredRepo {
+    pub local_repo: LocalRepo,
+    // where GitHub has it now, e.g. other-org/orders-api.
+    pub full_name: String,
+}
+
+// owner/repo of a remote on the GitHub of the team, None for other hosts.
+fn github_full_name(url: &str, github_team_repo_url: &Url) -> Option<String> {
+    let url = git::normalize_url(url);
+    let (host, full_name) = url.split_once('/')?;
+    let api_host = github_team_repo_url.host_str()?;
+    if api_host != host && api_host != format!("api.{}", host) {
+        return None;
+    }
+    Some(full_name.to_string())
+}
+
+// asks GitHub about every clone that matches no team repo. clones already decided on are
+// not asked about again.
+pub async fn find(
+    api_client: &ApiClient,
+    github_team_repo_url: &Url,
+    local_repos: &[LocalRepo],
+    remote_repos: &[RemoteRepo],
+    github_team_prefix: &String,
+    sync_state: &SyncState,
+) -> Vec<TransferredRepo> {
+    // an empty repo list is more likely a failed request than a team without repos.
+    if remote_repos.is_empty() {
+        return Vec::new();
+    }
+    let mut transferred = Vec::new();
+    for local_repo in local_repos {
+        if sync_state.unmanaged.contains(&local_repo.name)
+            || sync_state.external.contains(&local_repo.name)
+            || remote_repos.iter().any(|remote_repo| {
+                git::local_dir_name(remote_repo, github_team_prefix, &sync_state.directories)
+                    == local_repo.name
+            })
+        {
+            continue;
+        }
+        let Some(url) = git::remote_url(&local_repo.path) else {
+            continue;
+        };
+        if remote_repos
+            .iter()
+            .any(|remote_repo| git::is_same_remote(&url, remote_repo))
+        {
+            continue;
+        }
+        let Some(full_name) = github_full_name(&url, github_team_repo_url) else {
+            continue;
+        };
+        // deleted repos and repos the token can't see are not told apart, both stay as they are.
+        if let Ok(repo) = api::get_repo(api_client, github_team_repo_url, &full_name).await {
+            transferred.push(TransferredRepo {
+                local_repo: local_repo.clone(),
+                full_name: repo.full_name().unwrap_or(full_name),
+            });
+        }
+    }
+    transferred
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use crate::git;
+use crate::git::LocalRepo;
+use crate::git::RemoteRepo;
+use crate::provider;
+use crate::provider::Provider;
+use crate::state;
+use crate::state::SyncState;
+use crate::style;
+
+// directories of symlinks in the repo root dir, e.g. views/rust/<repo> -> ../../<repo>.
+pub const VIEWS_DIR: &str = "views";
+
+fn view_names(
+    remote_repo: &RemoteRepo,
+    dir_name: &str,
+    sync_state: &SyncState,
+) -> BTreeSet<String> {
+    let mut names: BTreeSet<String> = remote_repo.topics.iter().cloned().collect();
+    if let Some(language) = &remote_repo.language {
+        names.insert(language.to_lowercase());
+    }
+    for (tag, repos) in &sync_state.tags {
+        if repos.contains(&remote_repo.name) || repos.contains(dir_name) {
+            names.insert(tag.clone());
+        }
+    }
+    names
+}
+
+#[cfg(unix)]
+fn symlink_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
+
+// rebuilds the views dir from scratch, the links are the only thing in it.
+pub fn regenerate(
+    repo_root_dir: &PathBuf,
+    remote_repos: &Vec<RemoteRepo>,
+    github_team_prefix: &String,
+    sync_state: &SyncState,
+) -> Result<usize, std::io::Error> {
+    let views_dir = repo_root_dir.join(VIEWS_DIR);
+    if git::is_git_repo(&views_dir.to_string_lossy().to_string()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} is a repo", views_dir.display()),
+        ));
+    }
+    if views_dir.exists() {
+        fs::remove_dir_all(&views_dir)?;
+    }
+    fs::create_dir_all(&views_dir)?;
+    let local_repos: Vec<LocalRepo> = git::list_local_repos(repo_root_dir);
+    let mut link_count = 0;
+    for remote_repo in remote_repos {
+        let Some(local_repo) = git::find_local_repo(
+            remote_repo,
+            &local_repos,
+            github_team_prefix,
+            &sync_state.directories,
+        ) else {
+            continue;
+        };
+        for view_name in view_names(remote_repo, &local_repo.name, sync_state) {
+            // topics are lowercase by definition, languages and tags are sanitized the same way.
+            let view_name = view_name.replace(['/', '\\'], "-");
+            let view_dir = views_dir.join(&view_name);
+            fs::create_dir_all(&view_dir)?;
+            let target = PathBuf::from("..").join("..").join(&local_repo.name);
+            symlink_dir(&target, &view_dir.join(&local_repo.name))?;
+            link_count += 1;
+        }
+    }
+    Ok(link_count)
+}
+
+fn tag(views_args: &ArgMatches, add: bool) {
+    let tag = views_args.get_one::<String>("tag").unwrap();
+    let mut sync_state = state::load();
+    let repos = sync_state.tags.entry(tag.clone()).or_default();
+    for repo in views_args.get_many::<String>("repos").unwrap_or_default() {
+        if add {
+            repos.insert(repo.clone());
+        } else {
+            repos.remove(repo);
+        }
+    }
+    if repos.is_empty() {
+        sync_state.tags.remove(tag);
+    }
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+}
+
+pub async fn run(
+    provider: &dyn Provider,
+    repo_root_dir: &PathBuf,
+    github_team_prefix: &String,
+    views_args: &ArgMatches,
+) {
+    let remote_repos = provider::list_remote_repos(provider).await;
+    match views_args.subcommand() {
+        Some(("tag", tag_args)) => tag(tag_args, true),
+        Some(("untag", untag_args)) => tag(untag_args, false),
+        _ => (),
+    }
+    match regenerate(
+        repo_root_dir,
+        &remote_repos,
+        github_team_prefix,
+        &state::load(),
+    ) {
+        Ok(link_count) => println!(
+            "{}: {} links",
+            repo_root_dir.join(VIEWS_DIR).display(),
+            link_count
+        ),
+        Err(e) => println!(
+            "{}: {}",
+            style::paint("failed to update views", style::ERROR),
+            e
+        ),
+    }
+}
@@ -0,0 +1,310 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::state;
+use crate::style;
+
+// GitHub sends at most 25 MB per delivery.
+const MAX_PAYLOAD: usize = 25 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub listen: SocketAddr,
+    pub secret: Option<String>,
+    pub github_team_prefix: String,
+    pub repo_root_dir: PathBuf,
+}
+
+// what a delivery asks the daemon for.
+#[derive(Debug)]
+pub enum Delivery {
+    Sync(String),
+    // the daemon adopts the clone under the new name before syncing it, see adopt_renamed.
+    Renamed { old_name: String, new_name: String },
+}
+
+struct Request {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// just enough HTTP/1.1 for webhook deliveries: one POST with a Content-Length per connection.
+async fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let header_end = loop {
+        if let Some(index) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index;
+        }
+        if buffer.len() > 64 * 1024 {
+            return Err("headers too large".to_string());
+        }
+        let mut chunk = [0; 8192];
+        let read = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    if !lines.next().unwrap_or("").starts_with("POST ") {
+        return Err("only POST is supported".to_string());
+    }
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let mut request = Request {
+        headers,
+        body: buffer[header_end + 4..].to_vec(),
+    };
+    let content_length = request
+        .header("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+        .ok_or("missing Content-Length".to_string())?;
+    if content_length > MAX_PAYLOAD {
+        return Err("payload too large".to_string());
+    }
+    while request.body.len() < content_length {
+        let mut chunk = [0; 8192];
+        let read = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_string());
+        }
+        request.body.extend_from_slice(&chunk[..read]);
+    }
+    request.body.truncate(content_length);
+    Ok(request)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+// X-Hub-Signature-256 is the HMAC-SHA256 of the body with the webhook secret.
+fn is_signed(request: &Request, secret: &str) -> bool {
+    let Some(signature) = request
+        .header("x-hub-signature-256")
+        .and_then(|signature| signature.strip_prefix("sha256="))
+        .and_then(decode_hex)
+    else {
+        return false;
+    };
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    ring::hmac::verify(&key, &request.body, &signature).is_ok()
+}
+
+// a rename on GitHub keeps the existing clone under its directory, like an adoption,
+// so the targeted sync pulls it instead of cloning the repo a second time. only called by
+// the daemon while no sync runs, a running sync would overwrite the state when it ends.
+pub fn adopt_renamed(options: &Options, old_name: &str, new_name: &str) {
+    let mut sync_state = state::load();
+    let old_dir = sync_state
+        .directories
+        .remove(old_name)
+        .unwrap_or(old_name.replace(options.github_team_prefix.as_str(), ""));
+    if !options.repo_root_dir.join(&old_dir).exists() {
+        return;
+    }
+    sync_state.directories.insert(new_name.to_string(), old_dir);
+    sync_state.rename(old_name, new_name);
+    if let Err(e) = sync_state.save() {
+        println!(
+            "{}: {}",
+            style::paint("failed to save state", style::ERROR),
+            e
+        );
+    }
+}
+
+// what the daemon has to do for an event, None for events that need no sync.
+fn delivery(options: &Options, event: &str, payload: &serde_json::Value) -> Option<Delivery> {
+    let name = payload["repository"]["name"].as_str()?;
+    if !name.starts_with(options.github_team_prefix.as_str()) {
+        return None;
+    }
+    match (event, payload["action"].as_str()) {
+        ("push", _) => Some(Delivery::Sync(name.to_string())),
+        ("repository", Some("created" | "archived" | "unarchived" | "transferred")) => {
+            Some(Delivery::Sync(name.to_string()))
+        }
+        // a rename moves a clone to another repo, anyone who reaches an endpoint without
+        // a secret could point clones at repos of their choice.
+        ("repository", Some("renamed")) if options.secret.is_none() => None,
+        ("repository", Some("renamed")) => {
+            match payload["changes"]["repository"]["name"]["from"].as_str() {
+                Some(old_name) => Some(Delivery::Renamed {
+                    old_name: old_name.to_string(),
+                    new_name: name.to_string(),
+                }),
+                None => Some(Delivery::Sync(name.to_string())),
+            }
+        }
+        _ => None,
+    }
+}
+
+async fn handle(mut stream: TcpStream, options: Options, sender: UnboundedSender<Delivery>) {
+    let status = match read_request(&mut stream).await {
+        Err(_) => "400 Bad Request",
+        Ok(request)
+            if options
+                .secret
+                .as_ref()
+                .is_some_and(|secret| !is_signed(&request, secret)) =>
+        {
+            "401 Unauthorized"
+        }
+        Ok(request) => {
+            let event = request.header("x-github-event").unwrap_or("").to_string();
+            match serde_json::from_slice::<serde_json::Value>(&request.body) {
+                Ok(payload) => {
+                    if let Some(delivery) = delivery(&options, &event, &payload) {
+                        let repo = match &delivery {
+                            Delivery::Sync(repo) => repo,
+                            Delivery::Renamed { new_name, .. } => new_name,
+                        };
+                        println!("{}: {} webhook", style::paint(repo, style::WARNING), event);
+                        let _ = sender.send(delivery);
+                    }
+                    "204 No Content"
+                }
+                Err(_) => "400 Bad Request",
+            }
+        }
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+// accepts GitHub org webhooks and hands the affected team repos to the daemon.
+pub async fn listen(options: Options, sender: UnboundedSender<Delivery>) {
+    let listener = match TcpListener::bind(options.listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!(
+                "{}: {}",
+                style::paint(
+                    &format!("failed to listen on {}", options.listen),
+                    style::ERROR
+                ),
+                e
+            );
+            return;
+        }
+    };
+    if options.secret.is_none() {
+        println!(
+            "{}",
+            style::paint(
+                "webhooks are accepted without a secret, anyone who reaches the endpoint can trigger syncs, renames are ignored",
+                style::WARNING
+            )
+        );
+    }
+    println!("listening for webhooks on {}", options.listen);
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle(stream, options.clone(), sender.clone()));
+            }
+            Err(e) => println!(
+                "{}: {}",
+                style::paint("failed to accept webhook", style::ERROR),
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "It's a Secret to Everybody";
+    const BODY: &[u8] = b"Hello, World!";
+
+    fn request(signature: &str) -> Request {
+        Request {
+            headers: vec![("X-Hub-Signature-256".to_string(), signature.to_string())],
+            body: BODY.to_vec(),
+        }
+    }
+
+    fn signature(secret: &str) -> String {
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+        ring::hmac::sign(&key, BODY)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        // the example from the GitHub docs on validating webhook deliveries.
+        assert_eq!(
+            signature(SECRET),
+            "757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+        assert!(is_signed(
+            &request(&format!("sha256={}", signature(SECRET))),
+            SECRET
+        ));
+    }
+
+    #[test]
+    fn rejects_a_wrong_digest() {
+        assert!(!is_signed(
+            &request(&format!("sha256={}", signature("another secret"))),
+            SECRET
+        ));
+    }
+
+    #[test]
+    fn rejects_a_signature_without_the_sha256_prefix() {
+        assert!(!is_signed(&request(&signature(SECRET)), SECRET));
+    }
+
+    #[test]
+    fn rejects_an_odd_length_signature() {
+        let signature = signature(SECRET);
+        assert!(!is_signed(
+            &request(&format!("sha256={}", &signature[..signature.len() - 1])),
+            SECRET
+        ));
+    }
+
+    #[test]
+    fn rejects_a_missing_signature() {
+        let request = Request {
+            headers: Vec::new(),
+            body: BODY.to_vec(),
+        };
+        assert!(!is_signed(&request, SECRET));
+    }
+}